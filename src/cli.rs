@@ -10,6 +10,17 @@ use piglog::LogMode;
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+    /// Output format of read commands (list, diff, info, latest, show, verify, list-others)
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// Colored text for humans
+    Text,
+    /// Stable JSON for scripts
+    Json,
 }
 
 #[derive(Subcommand, Debug)]
@@ -30,6 +41,8 @@ pub enum Commands {
     ForceUnlock,
     /// Is Rebos unlocked? (Exit Status: (0 = Yes, 1 = No))
     IsUnlocked,
+    /// Show uncommitted changes, and whether the 'current' generation is built (Exit Status: (0 = Clean, 4 = Uncommitted changes, 5 = 'current' isn't built, 6 = Both))
+    Status,
     /// Manager commands
     Managers {
         #[command(subcommand)]
@@ -76,6 +89,17 @@ pub enum ManagerCommands {
         /// Remove all non specified items
         remove: bool,
     },
+    /// Add a manager from a bundled preset (apt, dnf, pacman, paru, yay, zypper, apk, xbps, flatpak, cargo, pipx, npm, snap, brew)
+    Add {
+        /// The preset to use
+        preset: String,
+        #[clap(long)]
+        /// Name of the manager (Default: the name of the preset)
+        name: Option<String>,
+        #[clap(long)]
+        /// Overwrite the manager if it already exists
+        force: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -84,6 +108,23 @@ pub enum ConfigCommands {
     Init,
     /// Check for warnings and errors in the Rebos configuration
     Check,
+    /// Fetch the configuration from a git repository (URL or path), and show what changed
+    Pull(ConfigPull),
+}
+
+#[derive(Parser, Debug)]
+pub struct ConfigPull {
+    /// The git repository to pull from (URL or path)
+    pub source: String,
+    #[clap(long)]
+    /// Commit a new generation afterwards
+    pub commit: bool,
+    #[clap(long, short, requires = "commit")]
+    /// Message for the new generation (Default: "Pull from <SOURCE>")
+    pub message: Option<String>,
+    #[clap(long, requires = "commit")]
+    /// Build the new generation afterwards
+    pub build: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -99,19 +140,36 @@ pub enum GenCommands {
     /// Automatically clean up the generations (clean-dups, align, etc...)
     TidyUp,
     /// Get information on the generation in the user's config
-    Info,
+    Info(GenInfo),
     /// Print out what the latest system generation number is
     Latest,
+    /// Show a system generation (items and metadata)
+    Show(GenShow),
     /// Delete older generations
     DeleteOld(GenDeleteOld),
     /// Delete a specific generation
     Delete(GenDelete),
+    /// Delete generations according to the retention policy in settings.toml
+    Gc(GenGc),
+    /// Write a generation, with the managers it uses, to one file that can be imported on another machine
+    Export(GenExport),
+    /// Create a new generation from a file made by 'gen export'
+    Import(GenImport),
+    /// Pin a generation, protecting it from being deleted
+    Pin(GenPin),
+    /// Unpin a generation, so it can be deleted again
+    Unpin(GenUnpin),
     /// The difference between 2 generations
     Diff {
-        /// Generation to act as base
-        old: usize,
+        /// Generation to act as base (Default with --user: the 'current' generation)
+        #[arg(required_unless_present = "user")]
+        old: Option<usize>,
         /// Generation to act as changes
-        new: usize,
+        #[arg(required_unless_present = "user", conflicts_with = "user")]
+        new: Option<usize>,
+        #[clap(long)]
+        /// Use the user config (the uncommitted changes) as the changes
+        user: bool,
     },
     /// Command related to the 'current' generation
     Current {
@@ -123,13 +181,17 @@ pub enum GenCommands {
 #[derive(Subcommand, Debug)]
 pub enum CurrentCommands {
     /// Build the 'current' generation (You can always roll back later)
-    Build,
+    Build(Build),
     /// Rollback to a previous generation (You still need to build after rolling back)
     Rollback(Rollback),
     /// Set the 'current' generation to the latest generation
     ToLatest,
     /// Set the 'current' generation to a specific generation
     Set(SetCurrent),
+    /// Check that the items of the built generation are installed (requires list command) (Exit Status: (0 = Yes, 2 = No))
+    Verify,
+    /// Reinstall the items of the built generation that aren't installed (requires list command)
+    Repair,
 }
 
 #[derive(ValueEnum, Debug, Clone, Copy)]
@@ -155,16 +217,91 @@ pub struct GenDelete {
     pub generation: usize,
 }
 
+#[derive(Parser, Debug)]
+pub struct GenShow {
+    /// The generation to show
+    pub generation: usize,
+}
+
+#[derive(Parser, Debug)]
+pub struct GenExport {
+    /// The generation to export
+    pub generation: usize,
+    #[clap(long = "file", short = 'o', value_name = "FILE")]
+    /// Where to write the bundle (Default: print it)
+    pub file: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct GenImport {
+    /// The bundle to import
+    pub file: String,
+    #[clap(long)]
+    /// Replace local managers that differ from the ones in the bundle
+    pub replace_managers: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct GenGc {
+    #[clap(long)]
+    /// Only print which generations would be deleted
+    pub dry_run: bool,
+    #[clap(long, short)]
+    /// Don't ask before deleting
+    pub yes: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct GenPin {
+    /// The generation to pin
+    pub generation: usize,
+}
+
+#[derive(Parser, Debug)]
+pub struct GenUnpin {
+    /// The generation to unpin
+    pub generation: usize,
+}
+
 #[derive(Parser, Debug)]
 pub struct GenDeleteOld {
     /// Starting at the oldest generation, how many should be deleted?
     pub how_many: usize,
 }
 
+#[derive(Parser, Debug)]
+pub struct GenInfo {
+    #[clap(long, value_name = "ITEM")]
+    /// Show which file (and which chain of imports) declared an item
+    pub explain: Option<String>,
+}
+
 #[derive(Parser, Debug)]
 pub struct Commit {
-    /// The commit message shows up in the list command
-    pub msg: String,
+    /// The commit message shows up in the list command (Opens $EDITOR if left out)
+    pub msg: Option<String>,
+    #[clap(long)]
+    /// Rewrite the latest generation (unless it is built) instead of creating a new one
+    pub amend: bool,
+    #[clap(long)]
+    /// Commit even if nothing changed since the latest generation
+    pub allow_empty: bool,
+    #[clap(long, conflicts_with = "allow_empty")]
+    /// Quietly do nothing (and succeed) if nothing changed since the latest generation
+    pub if_changed: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct Build {
+    #[clap(long)]
+    /// Continue an interrupted build from the step that failed
+    pub resume: bool,
+    #[clap(long)]
+    /// Print the commands the build would run, without running anything
+    pub dry_run: bool,
+    #[clap(long, short, default_value_t = 1, value_name = "N")]
+    /// Run up to N managers at the same time, if they aren't ordered against each other (Commands can't read input then)
+    pub jobs: usize,
 }
 
 #[derive(Parser, Debug)]
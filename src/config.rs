@@ -1,14 +1,17 @@
 #![allow(dead_code)]
 
 use fspp::*;
+use hashbrown::HashMap;
 use piglog::prelude::*;
 use piglog::*;
 use std::io;
 
 use crate::config;
 use crate::generation;
+use crate::git;
 use crate::library::*;
 use crate::places;
+use crate::presets;
 use crate::system;
 
 // Constants
@@ -22,12 +25,25 @@ imports = [
     # \"intensive_apps\",
 ]
 
+# Managers to leave out completely, even if an import uses them. (Example: [\"cargo\"])
+disabled_managers = []
+
 # Packages to be installed via the native package manager.
+# Items can be pinned to a version. (Example: { name = \"git\", version = \"1:2.43.0-1\" })
 [managers.system]
 items = [
     # \"git\",
 ]
 
+# Items to leave out, even if an import declares them.
+exclude = []
+
+# Items only used on some hosts. (Every given condition has to match.)
+# distro: 'ID' or 'ID_LIKE' from /etc/os-release, arch: output of 'uname -m', env: 'NAME' or 'NAME=value'
+# [[managers.system.when]]
+# distro = \"arch\"
+# items = [\"base-devel\"]
+
 # Packages to be installed via Flatpak.
 [managers.flatpak]
 items = [
@@ -41,6 +57,11 @@ items = [
 ]
 ";
 
+// Written by 'config pull' for machines the pulled config doesn't know. (Declares nothing, so no managers get pulled in.)
+const EMPTY_MACHINE_GEN: &str = "# Created by 'rebos config pull'. (Not tracked in git.)
+imports = []
+";
+
 const DEFAULT_PACKAGE_MANAGER_CONFIG: &str =
 "# --------------------------- #
 #    Manager Configuration    #
@@ -48,15 +69,28 @@ const DEFAULT_PACKAGE_MANAGER_CONFIG: &str =
 
 # Make sure to enter the exact command you use as the normal user!
 # That means including 'sudo' or 'doas' or whatever if the command needs it.
-# Where you would put items, enter '#:?'.
-
-# Example: add = \"sudo apt install #:?\"
-
-add = \"\" # Example: sudo apt install #:?
-remove = \"\" # Example: sudo apt remove #:?
+# Where you would put items, enter '#:q'. (They get quoted for the shell. '#:?' puts them in unquoted.)
+#
+# Other placeholders: (All of them get quoted for the shell.)
+#   #:items      - All items. (Same as '#:q'.)
+#   #:item       - A single item. (The command is run once per item. Example: systemctl --user enable #:item)
+#   #:hostname   - The hostname of the machine.
+#   #:username   - The user running Rebos.
+#   #:generation - The 'current' generation number.
+#   #:manager    - The name of this manager.
+#   #:env(NAME)  - The environment variable NAME. (Empty if it isn't set.)
+
+# Example: add = \"sudo apt install #:q\"
+
+add = \"\" # Example: sudo apt install #:q
+# add_versioned = \"\" # For items pinned to a version, where you would put the version, enter '#:qversion'. Example: sudo apt install #:q=#:qversion
+remove = \"\" # Example: sudo apt remove #:q
 sync = \"\" # Example: sudo apt update
 upgrade = \"\" # Example: sudo apt upgrade
 
+# after = [] # Managers to use before this one. (Example: [\"flatpak_remotes\"])
+# before = [] # Managers to use after this one.
+
 plural_name = \"system packages\"
 
 hook_name = \"system_packages\" # This is used in hooks. (Example: post_system_packages_add)
@@ -66,36 +100,36 @@ hook_name = \"system_packages\" # This is used in hooks. (Example: post_system_p
 # ------------------------------- #
 
 # many_args = BOOL: Can you supply many items as an argument? Example: 'sudo apt install git vim wget'
+# max_args_per_call = NUMBER: With 'many_args', supply at most this many items per command. (Default: no limit)
+# timeout = SECONDS: Kill add, remove, sync and upgrade commands that take longer than this. (Default: no timeout)
+# retries = NUMBER: How many more times to try a command that failed or timed out. (Default: 0)
+# retry_delay = SECONDS: How long to wait before trying again. (Default: 5)
+# shell = BOOL: Run commands through bash. If false, they are split on whitespace and run directly, so items are never interpreted. (Default: true)
 
 [config]
 many_args = true
+# max_args_per_call = 200
+# timeout = 1800
+# retries = 2
+# retry_delay = 10
+# shell = false
 ";
 
-const DEFAULT_FLATPAK_MANAGER_CONFIG: &str = "# Flatpak
-
-add = \"flatpak install #:?\"
-remove = \"flatpak uninstall #:?\"
-upgrade = \"flatpak upgrade\"
-
-plural_name = \"flatpaks\"
-
-hook_name = \"flatpaks\"
-
-[config]
-many_args = true
-";
-
-const DEFAULT_CARGO_MANAGER_CONFIG: &str = "# Cargo
-
-add = \"cargo install #:?\"
-remove = \"cargo uninstall #:?\"
-
-plural_name = \"crates\"
-
-hook_name = \"crates\"
-
-[config]
-many_args = true
+const DEFAULT_SETTINGS: &str = "# -------------------- #
+#    Rebos Settings    #
+# -------------------- #
+
+# Retention policy for 'rebos gen gc'. A generation is kept if any rule keeps it.
+# The 'current', built and pinned generations are always kept.
+[gc]
+# keep_last = 10 # Keep the newest 10 generations.
+# keep_days = 30 # Keep generations created in the last 30 days.
+# keep_built_history = 3 # Keep the last 3 generations that were built.
+
+# Git integration, for when this directory is a git repository.
+[git]
+record = false # Record the git revision of this directory in every new generation.
+auto_commit = false # Commit this directory (with the generation's message) when committing a generation.
 ";
 
 // This determinds if a function should
@@ -141,6 +175,21 @@ pub fn init_user_config() -> Result<(), io::Error> {
         }
     }
 
+    // Use the preset of the system package manager, if the distro is known.
+    let system_manager_config = match presets::for_distro(&system::distros()) {
+        Some(preset) => {
+            info!(
+                "Using the '{}' preset for the system package manager.",
+                preset.name
+            );
+            preset.config
+        }
+        None => {
+            note!("Unknown distro, so 'managers/system.toml' has to be filled in by hand.");
+            DEFAULT_PACKAGE_MANAGER_CONFIG
+        }
+    };
+
     let files = vec![
         (
             DEFAULT_USER_GEN,
@@ -154,17 +203,18 @@ pub fn init_user_config() -> Result<(), io::Error> {
                 .add_str("gen.toml"),
         ),
         (
-            DEFAULT_PACKAGE_MANAGER_CONFIG,
+            system_manager_config,
             places::base_user().add_str("managers/system.toml"),
         ),
         (
-            DEFAULT_FLATPAK_MANAGER_CONFIG,
+            presets::FLATPAK,
             places::base_user().add_str("managers/flatpak.toml"),
         ),
         (
-            DEFAULT_CARGO_MANAGER_CONFIG,
+            presets::CARGO,
             places::base_user().add_str("managers/cargo.toml"),
         ),
+        (DEFAULT_SETTINGS, places::settings()),
     ];
 
     for i in files.iter() {
@@ -182,6 +232,118 @@ pub fn init_user_config() -> Result<(), io::Error> {
     Ok(())
 }
 
+// Fetch the config from a git repository (URL or path), and return what changed in the resolved user generation.
+pub fn pull(source: &str) -> Result<HashMap<String, Vec<History>>, io::Error> {
+    let dir = places::base_user();
+
+    // Local paths are made absolute, because git runs inside of the config directory.
+    let source = match std::fs::canonicalize(source) {
+        Ok(o) => o.to_string_lossy().to_string(),
+        Err(_) => source.to_string(),
+    };
+
+    let is_repo = git::is_repo_root(&dir);
+
+    // Pulling would pull the bigger repository. (Like a dotfiles repository in the home directory.)
+    if !is_repo && dir.exists() && !directory::list_items(&dir)?.is_empty() && git::is_repo(&dir) {
+        error!(
+            "The config directory is inside of another git repository! ({})",
+            dir.to_string()
+        );
+        note!("Pull that repository instead, or make the config directory a git repository of its own.");
+
+        return Err(custom_error(
+            "Config directory is inside of another git repository!",
+        ));
+    }
+
+    let machine = dir
+        .add_str("machines")
+        .add_str(&system::hostname()?)
+        .add_str("gen.toml");
+
+    // An untouched machine file from an earlier pull isn't a local change, and is removed so the pull can't conflict with it.
+    if is_repo
+        && git::is_untracked(&dir, &machine)
+        && file::read(&machine).is_ok_and(|x| x == EMPTY_MACHINE_GEN)
+    {
+        fs_action::delete(&machine)?;
+    }
+
+    if is_repo && git::is_dirty(&dir) {
+        ensure_machine_gen(&machine)?;
+
+        error!(
+            "The config directory has uncommitted changes! (Commit or stash them in git first.)"
+        );
+        return Err(custom_error("Config directory has uncommitted changes!"));
+    }
+
+    let before = match config_for(Config::Generation, ConfigSide::User).exists() {
+        true => match generation::gen(ConfigSide::User) {
+            Ok(o) => o,
+            Err(_) => {
+                warning!("The config can't be resolved right now, so every item will show up as a change!");
+                generation::Generation::default()
+            }
+        },
+        false => generation::Generation::default(),
+    };
+
+    if is_repo {
+        info!("Pulling from: {source}");
+
+        let pulled = git::pull(&dir, &source);
+
+        ensure_machine_gen(&machine)?;
+        pulled?;
+    } else if !dir.exists() || directory::list_items(&dir)?.is_empty() {
+        info!("Cloning from: {source}");
+
+        let parent = dir.parent_path();
+
+        directory::create(&parent)?;
+
+        git::clone(&source, &parent, &dir)?;
+    } else {
+        error!(
+            "The config directory exists, but isn't a git repository! ({})",
+            dir.to_string()
+        );
+        note!("Move it somewhere else to clone into it, or make it a git repository with the same history.");
+
+        return Err(custom_error("Config directory isn't a git repository!"));
+    }
+
+    ensure_machine_gen(&machine)?;
+
+    if git::is_untracked(&dir, &machine) {
+        note!(
+            "The pulled config has nothing for this machine, so an empty machine config is used. ({})",
+            machine.to_string()
+        );
+    }
+
+    let after = generation::gen(ConfigSide::User)?;
+
+    Ok(history_gen(&before, &after)
+        .into_iter()
+        .filter(|(_, v)| !v.is_empty())
+        .collect())
+}
+
+// Create an empty machine file, if the config has nothing for this machine.
+fn ensure_machine_gen(machine: &Path) -> Result<(), io::Error> {
+    if machine.exists() {
+        return Ok(());
+    }
+
+    directory::create(&machine.parent_path())?;
+    file::write(EMPTY_MACHINE_GEN, machine)?;
+
+    Ok(())
+}
+
 // Return path for a config file.
 pub fn config_for(config: Config, side: ConfigSide) -> Path {
     return match config {
@@ -205,8 +367,10 @@ pub trait ConfigInfoToMessage {
 
 pub enum ConfigError {
     InvalidManager(String, Vec<String>),
+    MissingVersionedAdd(String),
     MissingMachine,
     FailedToDeserializeConfigGeneration,
+    OrderCycle(Vec<String>),
 }
 
 impl ConfigInfoToMessage for ConfigError {
@@ -222,18 +386,27 @@ impl ConfigInfoToMessage for ConfigError {
 
                 message
             }
+            Self::MissingVersionedAdd(ref man) => format!(
+                "Manager '{man}' has items pinned to a version, but no 'add_versioned' command!"
+            ),
             Self::MissingMachine => {
                 format!("Missing configuration for machine! (Machine specific gen.toml...)")
             }
             Self::FailedToDeserializeConfigGeneration => {
                 format!("Failed to deserialize config (user-side) generation!")
             }
+            Self::OrderCycle(ref cycle) => format!(
+                "Managers are ordered in a cycle! ({}) (In 'after' or 'before'.)",
+                cycle.join(" -> ")
+            ),
         }
     }
 }
 
 pub enum ConfigWarning {
     UnusedHook(String),
+    UnknownOrderManager(String, String),
+    Manager(String, String),
 }
 
 impl ConfigInfoToMessage for ConfigWarning {
@@ -242,6 +415,10 @@ impl ConfigInfoToMessage for ConfigWarning {
             Self::UnusedHook(ref hook) => format!(
                 "Hook '{hook}' is never used. (Doesn't match any manager 'hook_name' fields.)"
             ),
+            Self::UnknownOrderManager(ref man, ref other) => format!(
+                "Manager '{man}' is ordered against '{other}', which doesn't exist. (In 'after' or 'before'.)"
+            ),
+            Self::Manager(ref man, ref warning) => format!("Manager '{man}': {warning}"),
         }
     }
 }
@@ -256,9 +433,13 @@ pub fn check_config(
     let mut errors: Vec<ConfigError> = Vec::new();
     let mut warnings: Vec<ConfigWarning> = Vec::new();
 
-    match generation::gen(ConfigSide::User) {
-        Ok(_) => (),
-        Err(_) => errors.push(ConfigError::FailedToDeserializeConfigGeneration),
+    let user_gen = match generation::gen(ConfigSide::User) {
+        Ok(o) => Some(o),
+        Err(_) => {
+            errors.push(ConfigError::FailedToDeserializeConfigGeneration);
+
+            None
+        }
     };
 
     let managers = match crate::management::get_managers() {
@@ -293,6 +474,10 @@ pub fn check_config(
     for man in managers.iter() {
         match crate::management::load_manager_no_config_check(man) {
             Ok(o) => {
+                for i in o.check_warnings() {
+                    warnings.push(ConfigWarning::Manager(man.to_string(), i));
+                }
+
                 match o.check_config() {
                     Ok(_) => (),
                     Err(e) => errors.push(ConfigError::InvalidManager(man.to_string(), e)),
@@ -306,6 +491,25 @@ pub fn check_config(
         };
     }
 
+    // Check: Pinned items without a way to install them.
+    if let Some(ref user_gen) = user_gen {
+        for (man, items) in user_gen.managers.iter() {
+            if !items.items.iter().any(|x| x.version.is_some()) {
+                continue;
+            }
+
+            if let Some(loaded) = managers
+                .iter()
+                .position(|x| x == man)
+                .map(|x| &managers_loaded[x])
+            {
+                if loaded.add_versioned.is_none() {
+                    errors.push(ConfigError::MissingVersionedAdd(man.to_string()));
+                }
+            }
+        }
+    }
+
     // Check: Missing machine config.
     if places::base_user()
         .add_str(&format!("machines/{}", hostname))
@@ -316,6 +520,37 @@ pub fn check_config(
         errors.push(ConfigError::MissingMachine);
     }
 
+    // Check: Managers ordered against managers that don't exist, and order cycles.
+    let mut order_edges: Vec<generation::order::Edge> = Vec::new();
+
+    for (man, loaded) in managers.iter().zip(managers_loaded.iter()) {
+        for other in loaded.after.iter().chain(loaded.before.iter()) {
+            if !managers.contains(other) {
+                warnings.push(ConfigWarning::UnknownOrderManager(
+                    man.to_string(),
+                    other.to_string(),
+                ));
+            }
+        }
+
+        order_edges.extend(
+            loaded
+                .after
+                .iter()
+                .map(|x| (x.to_string(), man.to_string())),
+        );
+        order_edges.extend(
+            loaded
+                .before
+                .iter()
+                .map(|x| (man.to_string(), x.to_string())),
+        );
+    }
+
+    if let Err(cycle) = generation::order::topological_sort(&managers, &order_edges) {
+        errors.push(ConfigError::OrderCycle(cycle));
+    }
+
     // Check: Unused hooks.
     let stages_pre: [&str; 2] = ["pre", "post"];
     let stages_suf: [&str; 4] = ["add", "remove", "sync", "upgrade"];
@@ -367,3 +602,96 @@ pub fn print_errors_and_misc_info(errors: &[ConfigError], misc_info: &ConfigChec
         piglog::error!("{}", e.msg());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn pull_twice_from_a_local_repository() {
+        let dir = test_util::temp_dir("config-pull");
+        let _env = test_util::sandbox(&dir);
+
+        let remote = dir.join("remote.git");
+        let work = dir.join("work");
+
+        test_util::git(
+            &dir,
+            &["init", "--quiet", "--bare", remote.to_str().unwrap()],
+        );
+        test_util::git(
+            &dir,
+            &[
+                "clone",
+                "--quiet",
+                remote.to_str().unwrap(),
+                work.to_str().unwrap(),
+            ],
+        );
+
+        test_util::write(&work.join("gen.toml"), "imports = []\n");
+        test_util::commit(&work, "First");
+        test_util::git(&work, &["push", "--quiet", "origin", "HEAD"]);
+
+        // The first pull clones, and the machine isn't in the config.
+        let changes = pull(remote.to_str().unwrap()).unwrap();
+
+        assert!(changes.is_empty());
+        assert!(generation::gen(ConfigSide::User)
+            .unwrap()
+            .managers
+            .is_empty());
+
+        test_util::write(
+            &work.join("gen.toml"),
+            "imports = []\n\n[managers.system]\nitems = [\"git\"]\n",
+        );
+        test_util::commit(&work, "Second");
+        test_util::git(&work, &["push", "--quiet", "origin", "HEAD"]);
+
+        // The machine file from the first pull doesn't count as an uncommitted change.
+        let changes = pull(remote.to_str().unwrap()).unwrap();
+
+        assert_eq!(changes.keys().collect::<Vec<_>>(), vec!["system"]);
+        assert_eq!(changes["system"][0].item.name, "git");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn pull_refuses_a_config_inside_another_repository() {
+        let dir = test_util::temp_dir("config-pull-nested");
+        let _env = test_util::sandbox(&dir);
+
+        // Like a dotfiles repository, with the Rebos config in it.
+        let dotfiles = dir.join("config");
+
+        test_util::write(&dotfiles.join("rebos/gen.toml"), "imports = []\n");
+
+        let head = test_util::repo(&dotfiles, "Dotfiles");
+
+        // A remote that the dotfiles repository could be fast-forwarded to.
+        let remote = dir.join("remote");
+
+        test_util::git(
+            &dir,
+            &[
+                "clone",
+                "--quiet",
+                dotfiles.to_str().unwrap(),
+                remote.to_str().unwrap(),
+            ],
+        );
+        test_util::write(&remote.join("other.txt"), "newer\n");
+        test_util::commit(&remote, "Newer");
+
+        assert!(pull(remote.to_str().unwrap()).is_err());
+        assert_eq!(
+            test_util::git(&dotfiles, &["rev-parse", "HEAD"]).trim(),
+            head
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,232 @@
+use fspp::*;
+use piglog::prelude::*;
+use piglog::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+
+use super::metadata::{self, Metadata};
+use super::*;
+use crate::management::Manager;
+
+/// A generation together with everything needed to use it on another machine.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Bundle {
+    /// The version of Rebos that made the bundle.
+    pub rebos_version: String,
+    pub commit: String,
+    /// None if the generation was made before metadata existed.
+    pub metadata: Option<Metadata>,
+    /// None if there was no manager_order.toml.
+    pub manager_order: Option<ManagerOrder>,
+    /// Manager name -> the manager's TOML file, for every manager the generation uses.
+    pub managers: BTreeMap<String, toml::Table>,
+    pub generation: Generation,
+}
+
+// Bundle up a system generation.
+pub fn export(gen_id: usize) -> Result<Bundle, io::Error> {
+    let generation = get_gen_from_usize(gen_id)?;
+
+    let order_path = places::base_user().add_str("manager_order.toml");
+
+    let manager_order = match order_path.exists() {
+        true => match toml::from_str(&file::read(&order_path)?) {
+            Ok(o) => Some(o),
+            Err(e) => {
+                error!("Failed to deserialize manager_order.toml!");
+                error!("TOML Error: {e:#?}");
+
+                return Err(custom_error("Failed to deserialize manager_order.toml!"));
+            }
+        },
+        false => None,
+    };
+
+    let mut managers: BTreeMap<String, toml::Table> = BTreeMap::new();
+
+    for man in generation.managers.keys() {
+        let path = places::base_user().add_str(&format!("managers/{man}.toml"));
+
+        let table = match file::read(&path).map(|x| toml::from_str(&x)) {
+            Ok(Ok(o)) => o,
+            _ => {
+                error!("Failed to read manager file! ({man})");
+                return Err(custom_error("Failed to read manager file!"));
+            }
+        };
+
+        managers.insert(man.to_string(), table);
+    }
+
+    Ok(Bundle {
+        rebos_version: env!("CARGO_PKG_VERSION").to_string(),
+        commit: get_gen_commit_from_usize(gen_id)?,
+        metadata: metadata::read(gen_id)?,
+        manager_order,
+        managers,
+        generation,
+    })
+}
+
+pub fn to_string(bundle: &Bundle) -> Result<String, io::Error> {
+    match toml::to_string(bundle) {
+        Ok(o) => Ok(o),
+        Err(e) => {
+            error!("Failed to convert bundle to string! ({e})");
+            Err(custom_error("Failed to convert bundle to string!"))
+        }
+    }
+}
+
+pub fn read(path: &Path) -> Result<Bundle, io::Error> {
+    let bundle_string = match file::read(path) {
+        Ok(o) => o,
+        Err(e) => {
+            error!("Failed to read bundle! ({})", path.to_string());
+            return Err(e);
+        }
+    };
+
+    match toml::from_str(&bundle_string) {
+        Ok(o) => Ok(o),
+        Err(e) => {
+            error!("Failed to deserialize bundle!");
+            error!("TOML Error: {e:#?}");
+
+            Err(custom_error("Failed to deserialize bundle!"))
+        }
+    }
+}
+
+// Turn a bundle into a new generation, and set up the managers it needs. (Returns the new generation number.)
+pub fn import(bundle: &Bundle, replace_managers: bool) -> Result<usize, io::Error> {
+    abort_if_locked();
+
+    if let Some(ref meta) = bundle.metadata {
+        info!(
+            "Bundle of a generation made by {}@{} on {}.",
+            meta.username,
+            meta.hostname,
+            format_timestamp(meta.created)
+        );
+    }
+
+    // Check everything before writing anything.
+    for (man, table) in bundle.managers.iter() {
+        if man.is_empty() || man.starts_with('.') || man.contains('/') {
+            error!("Bundle has an invalid manager name! ({man})");
+            return Err(custom_error("Invalid manager name in bundle!"));
+        }
+
+        let manager: Result<Manager, toml::de::Error> =
+            toml::Value::Table(table.clone()).try_into();
+
+        if let Err(e) = manager {
+            error!("Bundle has an invalid manager! ({man})");
+            error!("TOML Error: {e:#?}");
+
+            return Err(custom_error("Invalid manager in bundle!"));
+        }
+    }
+
+    for man in bundle.generation.managers.keys() {
+        if !bundle.managers.contains_key(man) {
+            error!("Bundle is missing the manager '{man}', which the generation uses!");
+            return Err(custom_error("Bundle is missing a manager!"));
+        }
+    }
+
+    for (man, table) in bundle.managers.iter() {
+        let path = places::base_user().add_str(&format!("managers/{man}.toml"));
+
+        if path.exists() {
+            let local: Option<toml::Table> =
+                file::read(&path).ok().and_then(|x| toml::from_str(&x).ok());
+
+            if local.as_ref() == Some(table) {
+                continue;
+            }
+
+            if !replace_managers {
+                warning!("Manager '{man}' differs from the one in the bundle, keeping the local one! (Use '--replace-managers' to replace it.)");
+                continue;
+            }
+        }
+
+        write_toml(table, &path)?;
+
+        info!("Wrote manager: {man}");
+    }
+
+    if let Some(ref order) = bundle.manager_order {
+        let path = places::base_user().add_str("manager_order.toml");
+
+        if !path.exists() {
+            write_toml(order, &path)?;
+
+            info!("Wrote manager_order.toml");
+        } else if file::read(&path)
+            .ok()
+            .and_then(|x| toml::from_str::<ManagerOrder>(&x).ok())
+            .as_ref()
+            != Some(order)
+        {
+            warning!(
+                "manager_order.toml differs from the one in the bundle, keeping the local one!"
+            );
+        }
+    }
+
+    let generation_number = latest_number()? + 1;
+
+    let gen_dir = places::gens().add_str(&generation_number.to_string());
+
+    let parent = match places::gens().add_str("current").exists() {
+        true => Some(get_current()?),
+        false => None,
+    };
+
+    match directory::create(&gen_dir) {
+        Ok(_) => info!("Created generation directory."),
+        Err(e) => {
+            error!("Failed to create generation directory!");
+            return Err(e);
+        }
+    };
+
+    let written = write_toml(&bundle.generation, &gen_dir.add_str("gen.toml"))
+        .and_then(|_| file::write(&bundle.commit, &gen_dir.add_str("commit")))
+        .and_then(|_| metadata::write(generation_number, &Metadata::new(parent)?));
+
+    if let Err(e) = written {
+        error!("Failed to write generation files!");
+
+        let _ = fs_action::delete(&gen_dir);
+
+        return Err(e);
+    }
+
+    set_current(generation_number, true)?;
+
+    Ok(generation_number)
+}
+
+fn write_toml<T: Serialize>(value: &T, path: &Path) -> Result<(), io::Error> {
+    let string = match toml::to_string(value) {
+        Ok(o) => o,
+        Err(e) => {
+            error!("Failed to convert to TOML! ({e})");
+            return Err(custom_error("Failed to convert to TOML!"));
+        }
+    };
+
+    match file::write(&string, path) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error!("Failed to write file: {}", path.to_string());
+            Err(e)
+        }
+    }
+}
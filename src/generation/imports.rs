@@ -0,0 +1,237 @@
+use fspp::*;
+use piglog::prelude::*;
+use piglog::*;
+use serde::Serialize;
+use std::io;
+
+use super::{read_to_gen, Generation, GenerationUtils, Item};
+use crate::library::*;
+use crate::places;
+
+/// Where an item was declared.
+#[derive(Serialize, Clone, Debug)]
+pub struct Declaration {
+    pub manager: String,
+    pub item: Item,
+    /// The file that declared the item.
+    pub file: String,
+    /// The files that were read to get to that file, starting at the top and ending with the file itself.
+    pub chain: Vec<String>,
+    /// The file that left the item out, with 'exclude' or 'disabled_managers'. (None if the item is used.)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excluded_by: Option<String>,
+}
+
+/// Every declaration of every item, in the order they were read.
+pub type Provenance = Vec<Declaration>;
+
+/// Merges generation files and everything they import.
+pub struct Resolver {
+    generation: Generation,
+    provenance: Provenance,
+    /// Imports that were already merged. (A diamond import only gets merged once.)
+    visited: Vec<String>,
+    /// The chain of files being read right now. (Finding a file in here again means a cycle.)
+    stack: Vec<String>,
+    /// Excluded items. (Manager, item, file.)
+    exclusions: Vec<(String, String, String)>,
+    /// Disabled managers. (Manager, file.)
+    disabled: Vec<(String, String)>,
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Self {
+            generation: Generation::default(),
+            provenance: Vec::new(),
+            visited: Vec::new(),
+            stack: Vec::new(),
+            exclusions: Vec::new(),
+            disabled: Vec::new(),
+        }
+    }
+
+    /// Merge a top-level generation file (and its imports).
+    pub fn add_file(&mut self, path: &Path, label: &str) -> Result<(), io::Error> {
+        let file_gen = read_to_gen(path)?;
+
+        self.visit(file_gen, label)
+    }
+
+    pub fn finish(mut self) -> (Generation, Provenance) {
+        for i in self.provenance.iter_mut() {
+            let disabled_by = self.disabled.iter().find(|(man, _)| *man == i.manager);
+
+            let excluded_by = self
+                .exclusions
+                .iter()
+                .find(|(man, item, _)| *man == i.manager && *item == i.item.name);
+
+            i.excluded_by = match (disabled_by, excluded_by) {
+                (Some((_, file)), _) | (None, Some((_, _, file))) => Some(file.to_string()),
+                (None, None) => None,
+            };
+        }
+
+        (self.generation, self.provenance)
+    }
+
+    fn visit(&mut self, mut file_gen: Generation, label: &str) -> Result<(), io::Error> {
+        file_gen.resolve_conditions()?;
+
+        self.stack.push(label.to_string());
+
+        for (man, items) in file_gen.managers.iter() {
+            for i in items.items.iter() {
+                self.provenance.push(Declaration {
+                    manager: man.to_string(),
+                    item: i.clone(),
+                    file: label.to_string(),
+                    chain: self.stack.clone(),
+                    excluded_by: None,
+                });
+            }
+
+            for i in items.exclude.iter() {
+                self.exclusions
+                    .push((man.to_string(), i.to_string(), label.to_string()));
+            }
+        }
+
+        for i in file_gen.disabled_managers.iter() {
+            self.disabled.push((i.to_string(), label.to_string()));
+        }
+
+        let imports = std::mem::take(&mut file_gen.imports);
+
+        self.generation.extend(file_gen);
+
+        for i in imports.iter() {
+            let import_label = import_label(i);
+
+            if let Some(start) = self.stack.iter().position(|x| *x == import_label) {
+                let mut cycle: Vec<String> = self.stack[start..].to_vec();
+                cycle.push(import_label);
+
+                error!("Found an import cycle! ({})", cycle.join(" -> "));
+
+                return Err(custom_error("Found an import cycle!"));
+            }
+
+            if self.visited.contains(i) {
+                continue;
+            }
+
+            self.visited.push(i.to_string());
+
+            let import_gen = read_to_gen(&places::base_user().add_str(&import_label))?;
+
+            self.visit(import_gen, &import_label)?;
+        }
+
+        self.stack.pop();
+
+        Ok(())
+    }
+}
+
+// How an import is shown. (Relative to the config directory.)
+fn import_label(import: &str) -> String {
+    format!("imports/{import}.toml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    // Write generation files into the config directory, and merge 'gen.toml'.
+    fn resolve(name: &str, files: &[(&str, &str)]) -> Result<(Generation, Provenance), io::Error> {
+        let dir = test_util::temp_dir(name);
+        let _env = test_util::sandbox(&dir);
+
+        for (file, contents) in files {
+            test_util::write(&dir.join("config/rebos").join(file), contents);
+        }
+
+        let mut resolver = Resolver::new();
+
+        let resolved = resolver
+            .add_file(&places::base_user().add_str("gen.toml"), "gen.toml")
+            .map(|_| resolver.finish());
+
+        let _ = std::fs::remove_dir_all(&dir);
+
+        resolved
+    }
+
+    #[test]
+    fn diamond_imports_are_merged_once() {
+        let (gen, provenance) = resolve(
+            "imports-diamond",
+            &[
+                ("gen.toml", "imports = [\"a\", \"b\"]\n"),
+                ("imports/a.toml", "imports = [\"c\"]\n"),
+                ("imports/b.toml", "imports = [\"c\"]\n"),
+                (
+                    "imports/c.toml",
+                    "imports = []\n\n[managers.system]\nitems = [\"git\"]\n",
+                ),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(gen.managers["system"].items.len(), 1);
+        assert_eq!(provenance.len(), 1);
+        assert_eq!(provenance[0].file, "imports/c.toml");
+        assert_eq!(
+            provenance[0].chain,
+            vec!["gen.toml", "imports/a.toml", "imports/c.toml"]
+        );
+    }
+
+    #[test]
+    fn import_cycles_are_errors() {
+        let resolved = resolve(
+            "imports-cycle",
+            &[
+                ("gen.toml", "imports = [\"a\"]\n"),
+                ("imports/a.toml", "imports = [\"b\"]\n"),
+                ("imports/b.toml", "imports = [\"a\"]\n"),
+            ],
+        );
+
+        assert!(resolved.is_err());
+    }
+
+    #[test]
+    fn exclusions_are_recorded_on_declarations() {
+        let (_, provenance) = resolve(
+            "imports-exclude",
+            &[
+                (
+                    "gen.toml",
+                    "imports = [\"base\"]\ndisabled_managers = [\"cargo\"]\n\n[managers.system]\nitems = [\"git\"]\nexclude = [\"nano\"]\n",
+                ),
+                (
+                    "imports/base.toml",
+                    "imports = []\n\n[managers.system]\nitems = [\"nano\"]\n\n[managers.cargo]\nitems = [\"bacon\"]\n",
+                ),
+            ],
+        )
+        .unwrap();
+
+        let excluded_by = |name: &str| {
+            provenance
+                .iter()
+                .find(|x| x.item.name == name)
+                .unwrap()
+                .excluded_by
+                .clone()
+        };
+
+        assert_eq!(excluded_by("git"), None);
+        assert_eq!(excluded_by("nano").as_deref(), Some("gen.toml"));
+        assert_eq!(excluded_by("bacon").as_deref(), Some("gen.toml"));
+    }
+}
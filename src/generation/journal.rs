@@ -0,0 +1,104 @@
+use fspp::*;
+use piglog::prelude::*;
+use piglog::*;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+use crate::library::*;
+use crate::places;
+
+/// Record of which build steps have already finished, so an interrupted build can be resumed.
+#[derive(PartialEq, Serialize, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct Journal {
+    /// The generation that is being built.
+    pub generation: usize,
+    /// The generation that was built when the build started. (None if it is the first build.)
+    pub from: Option<usize>,
+    /// IDs of the steps that finished successfully.
+    pub completed: Vec<String>,
+}
+
+impl Journal {
+    pub fn new(generation: usize, from: Option<usize>) -> Self {
+        Self {
+            generation,
+            from,
+            completed: Vec::new(),
+        }
+    }
+
+    pub fn is_done(&self, step_id: &str) -> bool {
+        self.completed.iter().any(|x| x == step_id)
+    }
+
+    /// Mark a step as finished, and write the journal to disk right away.
+    pub fn mark_done(&mut self, step_id: &str) -> Result<(), io::Error> {
+        self.completed.push(step_id.to_string());
+
+        write(self)
+    }
+}
+
+// Read the journal of an interrupted build, if there is one.
+pub fn read() -> Result<Option<Journal>, io::Error> {
+    let path = places::build_journal();
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let journal_string = match file::read(&path) {
+        Ok(o) => o,
+        Err(e) => {
+            error!("Failed to read build journal!");
+            return Err(e);
+        }
+    };
+
+    match toml::from_str(&journal_string) {
+        Ok(o) => Ok(Some(o)),
+        Err(e) => {
+            error!("Failed to deserialize build journal! (Maybe it is corrupted?)");
+            error!("TOML Error: {e:#?}");
+
+            Err(custom_error("Failed to deserialize build journal!"))
+        }
+    }
+}
+
+// Write the journal to disk.
+pub fn write(journal: &Journal) -> Result<(), io::Error> {
+    let journal_string = match toml::to_string(journal) {
+        Ok(o) => o,
+        Err(_e) => {
+            error!("Failed to convert build journal to string!");
+            return Err(custom_error("Failed to convert build journal to string!"));
+        }
+    };
+
+    match file::write(&journal_string, &places::build_journal()) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error!("Failed to write build journal!");
+            Err(e)
+        }
+    }
+}
+
+// Delete the journal. (The build finished.)
+pub fn clear() -> Result<(), io::Error> {
+    let path = places::build_journal();
+
+    if path.exists() {
+        match fs_action::delete(&path) {
+            Ok(_) => (),
+            Err(e) => {
+                error!("Failed to delete build journal!");
+                return Err(e);
+            }
+        };
+    }
+
+    Ok(())
+}
@@ -1,7 +1,11 @@
 use std::io;
 use piglog::prelude::*;
+use colored::Colorize;
 
+use crate::library::{custom_error, now};
 use crate::lock;
+use crate::settings;
+use super::metadata;
 
 pub fn tidy_up() -> Result<(), io::Error> {
     lock::abort_if_locked();
@@ -30,7 +34,16 @@ pub fn clean_dups(verbose: bool) -> Result<usize, io::Error> {
         let generation = super::get_gen_from_usize(i)?;
 
         if let Some(ref s) = comparison {
-            if generation == *s {
+            if generation == *s && super::is_pinned(i) {
+                if verbose {
+                    piglog::info!("Keeping duplicate generation {i}, because it is pinned.");
+                }
+
+                comparison = Some(generation);
+                comp_num = Some(i);
+            }
+
+            else if generation == *s {
                 let current = super::is_current(i)?;
                 let built = super::is_built(i)?;
 
@@ -122,3 +135,108 @@ pub fn align(verbose: bool) -> Result<usize, io::Error> {
 
     return Ok(moved);
 }
+
+// Delete generations according to the retention policy in settings.toml.
+pub fn gc(dry_run: bool, yes: bool) -> Result<usize, io::Error> {
+    lock::abort_if_locked();
+
+    let policy = settings::load()?.gc;
+
+    if policy.keep_last.is_none() && policy.keep_days.is_none() && policy.keep_built_history.is_none() {
+        piglog::error!("No retention policy is set! (Set 'keep_last', 'keep_days' or 'keep_built_history' under [gc] in settings.toml.)");
+
+        return Err(custom_error("No retention policy is set!"));
+    }
+
+    let generations = super::sort_list_vector(&super::list()?);
+
+    // (Build time, generation number), most recently built first.
+    let mut built_history: Vec<(u64, usize)> = generations
+        .iter()
+        .filter_map(|x| metadata::read_built_at(x.number).map(|t| (t, x.number)))
+        .collect();
+    built_history.sort();
+    built_history.reverse();
+
+    let now = now();
+
+    let mut to_delete: Vec<usize> = Vec::new();
+
+    for (index, i) in generations.iter().enumerate() {
+        let from_newest = generations.len() - index;
+
+        let reason: Option<String> = if i.current {
+            Some(String::from("current"))
+        }
+
+        else if i.built {
+            Some(String::from("built"))
+        }
+
+        else if i.pinned {
+            Some(String::from("pinned"))
+        }
+
+        else if policy.keep_last.is_some_and(|n| from_newest <= n) {
+            Some(format!("one of the last {}", policy.keep_last.unwrap()))
+        }
+
+        else if let Some(days) = policy.keep_days.filter(|d| match i.created {
+            Some(created) => now.saturating_sub(created) <= d * 86400,
+            None => true,
+        }) {
+            match i.created {
+                Some(_) => Some(format!("newer than {days} days")),
+                None => Some(String::from("unknown age")),
+            }
+        }
+
+        else if policy.keep_built_history.is_some_and(|n| built_history.iter().take(n).any(|x| x.1 == i.number)) {
+            Some(format!("one of the last {} built", policy.keep_built_history.unwrap()))
+        }
+
+        else {
+            None
+        };
+
+        match reason {
+            Some(r) => piglog::generic!("{} ... {} ({r})", i.number, "keep".bright_green().bold()),
+            None => {
+                piglog::generic!("{} ... {}", i.number, "delete".bright_red().bold());
+
+                to_delete.push(i.number);
+            },
+        };
+    }
+
+    if to_delete.is_empty() {
+        piglog::info!("Nothing to delete!");
+
+        return Ok(0);
+    }
+
+    if dry_run {
+        piglog::info!("Would delete {} generations. (Dry run, nothing was deleted.)", to_delete.len());
+
+        return Ok(0);
+    }
+
+    if !yes && !crate::bool_question(format!("Delete {} generations?", to_delete.len()), false) {
+        piglog::info!("Aborting...");
+
+        return Ok(0);
+    }
+
+    for i in to_delete.iter() {
+        match super::delete(*i, true) {
+            Ok(_) => (),
+            Err(e) => {
+                piglog::fatal!("Failed to delete generation: {i}");
+
+                return Err(e);
+            },
+        };
+    }
+
+    Ok(to_delete.len())
+}
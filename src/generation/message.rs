@@ -0,0 +1,113 @@
+use fspp::*;
+use piglog::prelude::*;
+use piglog::*;
+use std::io;
+
+use super::Generation;
+use crate::library::*;
+use crate::places;
+
+// Let the user write a commit message in their editor. (Lines starting with '#' are left out.)
+pub fn edit(
+    initial: &str,
+    previous: Option<(usize, &Generation)>,
+    next: &Generation,
+) -> Result<String, io::Error> {
+    let path = places::commit_message();
+
+    match file::write(&template(initial, previous, next), &path) {
+        Ok(_) => (),
+        Err(e) => {
+            error!("Failed to write commit message file!");
+            return Err(e);
+        }
+    };
+
+    let editor = editor();
+
+    if !run_command(&format!("{editor} \"{}\"", path.to_string())) {
+        error!("Editor exited with an error! ({editor})");
+        return Err(custom_error("Editor exited with an error!"));
+    }
+
+    let message = match file::read(&path) {
+        Ok(o) => strip(&o),
+        Err(e) => {
+            error!("Failed to read commit message file!");
+            return Err(e);
+        }
+    };
+
+    let _ = fs_action::delete(&path);
+
+    Ok(message)
+}
+
+// The first line of a commit message. (What gets shown in lists.)
+pub fn first_line(message: &str) -> &str {
+    message.lines().next().unwrap_or("")
+}
+
+// $VISUAL, then $EDITOR, then vi.
+fn editor() -> String {
+    std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|x| !x.trim().is_empty())
+        .unwrap_or(String::from("vi"))
+}
+
+fn template(initial: &str, previous: Option<(usize, &Generation)>, next: &Generation) -> String {
+    let mut text = format!("{initial}\n\n");
+
+    text.push_str(
+        "# Write a message for this generation. The first line is shown in 'rebos gen list'.\n",
+    );
+    text.push_str(
+        "# Lines starting with '#' are ignored, and an empty message aborts the commit.\n",
+    );
+    text.push_str("#\n");
+
+    let history = match previous {
+        Some((number, generation)) => {
+            text.push_str(&format!("# Changes since generation {number}:\n"));
+            history_gen(generation, next)
+        }
+        None => {
+            text.push_str("# Items in this generation:\n");
+            history_gen(&Generation::default(), next)
+        }
+    };
+
+    let mut managers: Vec<&String> = history.keys().filter(|x| !history[*x].is_empty()).collect();
+    managers.sort();
+
+    if managers.is_empty() {
+        text.push_str("#   (No changes.)\n");
+    }
+
+    for m in managers {
+        text.push_str(&format!("#   {m}:\n"));
+
+        for h in history[m].iter() {
+            let symbol = match h.mode {
+                HistoryMode::Add => '+',
+                HistoryMode::Remove => '-',
+                HistoryMode::Change => '~',
+            };
+
+            text.push_str(&format!("#     {symbol} {}\n", h.line()));
+        }
+    }
+
+    text
+}
+
+fn strip(text: &str) -> String {
+    text.lines()
+        .filter(|x| !x.starts_with('#'))
+        .collect::<Vec<&str>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
@@ -0,0 +1,207 @@
+use fspp::*;
+use piglog::prelude::*;
+use piglog::*;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+use crate::git;
+use crate::library::*;
+use crate::places;
+use crate::settings;
+use crate::system;
+
+/// Information about when and where a generation was made. (Stored in 'meta.toml' next to 'gen.toml'.)
+#[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Metadata {
+    /// When the generation was created. (Unix timestamp, in seconds.)
+    pub created: u64,
+    pub hostname: String,
+    pub username: String,
+    /// The version of Rebos that created the generation.
+    pub rebos_version: String,
+    /// The 'current' generation at the time of the commit. (None if there was none.)
+    pub parent: Option<usize>,
+    /// The git revision of the config directory. (Only recorded if enabled in settings.toml.)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitRevision>,
+}
+
+/// Which commit the config directory was at.
+#[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct GitRevision {
+    pub head: String,
+    /// Were there uncommitted changes?
+    pub dirty: bool,
+}
+
+impl Metadata {
+    /// Metadata for a generation being created right now.
+    pub fn new(parent: Option<usize>) -> Result<Self, io::Error> {
+        Ok(Self {
+            created: now(),
+            hostname: system::hostname()?,
+            username: username(),
+            rebos_version: env!("CARGO_PKG_VERSION").to_string(),
+            parent,
+            git: None,
+        })
+    }
+}
+
+// Commit the config directory and/or get its git revision, depending on the git settings.
+pub fn git_revision(msg: &str) -> Result<Option<GitRevision>, io::Error> {
+    let git_settings = settings::load()?.git;
+
+    if !git_settings.record && !git_settings.auto_commit {
+        return Ok(None);
+    }
+
+    let dir = places::base_user();
+
+    if !git::is_repo(&dir) {
+        warning!("Git integration is enabled, but the config directory isn't a git repository!");
+        return Ok(None);
+    }
+
+    if git_settings.auto_commit && git::is_dirty(&dir) {
+        git::commit_all(&dir, msg)?;
+
+        info!("Committed the config directory in git.");
+    }
+
+    if !git_settings.record {
+        return Ok(None);
+    }
+
+    match git::head(&dir) {
+        Some(head) => Ok(Some(GitRevision {
+            head,
+            dirty: git::is_dirty(&dir),
+        })),
+        None => {
+            warning!(
+                "The config's git repository has no commits, so there is no revision to record!"
+            );
+            Ok(None)
+        }
+    }
+}
+
+fn path(gen_id: usize) -> Path {
+    places::gens()
+        .add_str(&gen_id.to_string())
+        .add_str("meta.toml")
+}
+
+// Read the metadata of a generation. (None for generations made before metadata existed.)
+pub fn read(gen_id: usize) -> Result<Option<Metadata>, io::Error> {
+    let path = path(gen_id);
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let meta_string = match file::read(&path) {
+        Ok(o) => o,
+        Err(e) => {
+            error!("Failed to read metadata of generation {gen_id}!");
+            return Err(e);
+        }
+    };
+
+    match toml::from_str(&meta_string) {
+        Ok(o) => Ok(Some(o)),
+        Err(e) => {
+            error!("Failed to deserialize metadata of generation {gen_id}!");
+            error!("TOML Error: {e:#?}");
+
+            Err(custom_error("Failed to deserialize generation metadata!"))
+        }
+    }
+}
+
+pub fn to_string(meta: &Metadata) -> Result<String, io::Error> {
+    match toml::to_string(meta) {
+        Ok(o) => Ok(o),
+        Err(_e) => {
+            error!("Failed to convert generation metadata to string!");
+            Err(custom_error(
+                "Failed to convert generation metadata to string!",
+            ))
+        }
+    }
+}
+
+// Write the metadata of a generation.
+pub fn write(gen_id: usize, meta: &Metadata) -> Result<(), io::Error> {
+    match file::write(&to_string(meta)?, &path(gen_id)) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error!("Failed to write metadata of generation {gen_id}!");
+            Err(e)
+        }
+    }
+}
+
+fn built_at_path(gen_id: usize) -> Path {
+    places::gens()
+        .add_str(&gen_id.to_string())
+        .add_str("built_at")
+}
+
+// Record that a generation was just built.
+pub fn write_built_at(gen_id: usize) -> Result<(), io::Error> {
+    match file::write(&now().to_string(), &built_at_path(gen_id)) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            error!("Failed to record build time of generation {gen_id}!");
+            Err(e)
+        }
+    }
+}
+
+// When a generation was last built. (None if it never was, or it was before build times were recorded.)
+pub fn read_built_at(gen_id: usize) -> Option<u64> {
+    file::read(&built_at_path(gen_id))
+        .ok()
+        .and_then(|x| x.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn git_revision_commits_and_records_the_config() {
+        let root = test_util::temp_dir("git-revision");
+        let _env = test_util::sandbox(&root);
+
+        let config = root.join("config");
+
+        test_util::repo(&config, "Initial");
+        test_util::write(
+            &config.join("rebos/settings.toml"),
+            "[git]\nrecord = true\nauto_commit = true\n",
+        );
+
+        let revision = git_revision("Generation message").unwrap().unwrap();
+
+        assert_eq!(
+            revision.head,
+            test_util::git(&config, &["rev-parse", "HEAD"]).trim()
+        );
+        assert!(!revision.dirty);
+        assert_eq!(
+            test_util::git(&config, &["log", "-1", "--format=%s"]).trim(),
+            "Generation message"
+        );
+
+        // Nothing changed, so there is no new commit, and the same revision is recorded.
+        assert_eq!(git_revision("Another message").unwrap().unwrap(), revision);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -1,6 +1,13 @@
 #![allow(dead_code)]
 
+pub mod bundle;
+pub mod imports;
+pub mod journal;
 pub mod management;
+pub mod message;
+pub mod metadata;
+pub mod order;
+pub mod parallel;
 
 use colored::Colorize;
 use fspp::*;
@@ -40,18 +47,131 @@ impl Default for ManagerOrder {
     }
 }
 
-#[derive(PartialEq, Serialize, Deserialize, Debug)]
+#[derive(PartialEq, Serialize, Deserialize, Debug, Default)]
 #[serde(deny_unknown_fields, default)]
 pub struct Items {
-    pub items: Vec<String>,
+    pub items: Vec<Item>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub when: Vec<When>,
+    /// Names of items to leave out, even if an import declares them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
 }
 
-impl Default for Items {
-    fn default() -> Self {
-        Self { items: Vec::new() }
+/// Items that are only used on hosts matching every given condition.
+#[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct When {
+    /// Matches 'ID' or 'ID_LIKE' from /etc/os-release.
+    pub distro: Option<String>,
+    /// Matches `uname -m`.
+    pub arch: Option<String>,
+    /// An environment variable that has to be set. ('NAME' or 'NAME=value')
+    pub env: Option<String>,
+    pub items: Vec<Item>,
+}
+
+impl When {
+    pub fn matches(&self, facts: &system::HostFacts) -> bool {
+        if let Some(ref distro) = self.distro {
+            if !facts.distros.contains(&distro.to_lowercase()) {
+                return false;
+            }
+        }
+
+        if let Some(ref arch) = self.arch {
+            if *arch != facts.arch {
+                return false;
+            }
+        }
+
+        if let Some(ref env) = self.env {
+            let matches = match env.split_once('=') {
+                Some((name, value)) => std::env::var(name).is_ok_and(|x| x == value),
+                None => std::env::var(env).is_ok_and(|x| !x.is_empty()),
+            };
+
+            if !matches {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// An item, optionally pinned to a version.
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
+#[serde(from = "ItemRepr", into = "ItemRepr")]
+pub struct Item {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+// How an item is written in TOML. ("ripgrep" or { name = "ripgrep", version = "14.1.0" })
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ItemRepr {
+    Name(String),
+    Versioned { name: String, version: String },
+}
+
+impl From<ItemRepr> for Item {
+    fn from(repr: ItemRepr) -> Self {
+        match repr {
+            ItemRepr::Name(name) => Self {
+                name,
+                version: None,
+            },
+            ItemRepr::Versioned { name, version } => Self {
+                name,
+                version: Some(version),
+            },
+        }
+    }
+}
+
+impl From<Item> for ItemRepr {
+    fn from(item: Item) -> Self {
+        match item.version {
+            Some(version) => Self::Versioned {
+                name: item.name,
+                version,
+            },
+            None => Self::Name(item.name),
+        }
+    }
+}
+
+impl From<String> for Item {
+    fn from(name: String) -> Self {
+        Self {
+            name,
+            version: None,
+        }
+    }
+}
+
+impl From<&str> for Item {
+    fn from(name: &str) -> Self {
+        Self::from(name.to_string())
+    }
+}
+
+impl std::fmt::Display for Item {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.version {
+            Some(ref version) => write!(f, "{} ({})", self.name, version),
+            None => write!(f, "{}", self.name),
+        }
     }
 }
 
+// Turn a list of item names into unversioned items.
+pub fn items_from_names(names: Vec<String>) -> Vec<Item> {
+    names.into_iter().map(Item::from).collect()
+}
+
 pub mod legacy_1 {
     use serde::{Deserialize, Serialize};
 
@@ -97,16 +217,27 @@ pub mod legacy_1 {
 
             gen.imports = self.imports;
 
-            gen.managers
-                .insert("system".to_string(), super::Items { items: self.pkgs });
+            gen.managers.insert(
+                "system".to_string(),
+                super::Items {
+                    items: super::items_from_names(self.pkgs),
+                    ..Default::default()
+                },
+            );
             gen.managers.insert(
                 "flatpak".to_string(),
                 super::Items {
-                    items: self.flatpaks,
+                    items: super::items_from_names(self.flatpaks),
+                    ..Default::default()
+                },
+            );
+            gen.managers.insert(
+                "cargo".to_string(),
+                super::Items {
+                    items: super::items_from_names(self.crates),
+                    ..Default::default()
                 },
             );
-            gen.managers
-                .insert("cargo".to_string(), super::Items { items: self.crates });
 
             gen
         }
@@ -156,7 +287,13 @@ pub mod legacy_2 {
             gen.imports = self.imports;
 
             for (key, value) in self.pkg_managers.into_iter() {
-                gen.managers.insert(key, super::Items { items: value.pkgs });
+                gen.managers.insert(
+                    key,
+                    super::Items {
+                        items: super::items_from_names(value.pkgs),
+                        ..Default::default()
+                    },
+                );
             }
 
             gen
@@ -177,6 +314,9 @@ pub mod legacy_2 {
 #[serde(deny_unknown_fields, default)]
 pub struct Generation {
     pub imports: Vec<String>,
+    /// Managers to leave out completely, even if an import uses them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub disabled_managers: Vec<String>,
     pub managers: HashMap<String, Items>,
 }
 
@@ -184,6 +324,7 @@ impl Default for Generation {
     fn default() -> Generation {
         Generation {
             imports: Vec::new(),
+            disabled_managers: Vec::new(),
             managers: HashMap::new(),
         }
     }
@@ -192,81 +333,91 @@ impl Default for Generation {
 impl GenerationUtils for Generation {
     fn extend(&mut self, other_gen: Generation) {
         self.imports.extend(other_gen.imports);
+        self.disabled_managers.extend(other_gen.disabled_managers);
 
-        for i in other_gen.managers.keys() {
-            match self.managers.get_mut(i) {
-                Some(s) => s
-                    .items
-                    .extend(other_gen.managers.get(i).unwrap().items.clone()),
-                None => {
-                    self.managers
-                        .insert(i.to_string(), Items { items: Vec::new() });
-                    self.managers
-                        .get_mut(i)
-                        .unwrap()
-                        .items
-                        .extend(other_gen.managers.get(i).unwrap().items.clone());
+        for (i, other_items) in other_gen.managers.into_iter() {
+            let items = self.managers.entry(i).or_default();
+
+            items.items.extend(other_items.items);
+            items.when.extend(other_items.when);
+            items.exclude.extend(other_items.exclude);
+        }
+    }
+
+    fn apply_overrides(&mut self) {
+        for i in std::mem::take(&mut self.disabled_managers) {
+            self.managers.remove(&i);
+        }
+
+        for items in self.managers.values_mut() {
+            let exclude = std::mem::take(&mut items.exclude);
+
+            items.items.retain(|x| !exclude.contains(&x.name));
+        }
+    }
+
+    fn resolve_conditions(&mut self) -> Result<(), io::Error> {
+        if self.managers.values().all(|x| x.when.is_empty()) {
+            return Ok(());
+        }
+
+        let facts = system::host_facts()?;
+
+        for items in self.managers.values_mut() {
+            for w in std::mem::take(&mut items.when) {
+                if w.matches(facts) {
+                    items.items.extend(w.items);
                 }
-            };
+            }
         }
+
+        Ok(())
     }
 }
 
 pub trait GenerationUtils {
     /// Extend all of the fields from one Generation object to another, another being the caller
     fn extend(&mut self, other_gen: Generation);
+    /// Move the items of matching 'when' blocks into the regular items, and drop the rest
+    fn resolve_conditions(&mut self) -> Result<(), io::Error>;
+    /// Drop disabled managers and excluded items (Only makes sense once all imports are merged)
+    fn apply_overrides(&mut self);
 }
 
 // Return generation structure for...
 pub fn gen(side: ConfigSide) -> Result<Generation, io::Error> {
-    let mut generation = match read_to_gen(&config_for(Config::Generation, side)) {
-        Ok(o) => o,
-        Err(e) => return Err(e),
+    Ok(gen_with_provenance(side)?.0)
+}
+
+// Return generation structure for... (And where each item was declared.)
+pub fn gen_with_provenance(
+    side: ConfigSide,
+) -> Result<(Generation, imports::Provenance), io::Error> {
+    let mut resolver = imports::Resolver::new();
+
+    let label = match side {
+        ConfigSide::User => String::from("gen.toml"),
+        ConfigSide::System => config_for(Config::Generation, side).to_string(),
     };
 
+    resolver.add_file(&config_for(Config::Generation, side), &label)?;
+
     let system_hostname = match system::hostname() {
         Ok(o) => o,
         Err(e) => return Err(e),
     };
 
     if side == ConfigSide::User {
-        generation.extend(read_to_gen(
-            &places::base_user()
-                .add_str("machines")
-                .add_str(&system_hostname)
-                .add_str("gen.toml"),
-        )?);
-    }
+        let machine_label = format!("machines/{system_hostname}/gen.toml");
 
-    while generation.imports.len() > 0 {
-        let gen_imports = generation.imports.clone();
-
-        for i in gen_imports.iter() {
-            let i_gen = read_to_gen(
-                &places::base_user()
-                    .add_str("imports")
-                    .add_str(&format!("{i}.toml")),
-            )?;
-
-            generation.extend(i_gen);
-        }
-
-        let after_gen_imports = generation.imports.clone();
+        resolver.add_file(&places::base_user().add_str(&machine_label), &machine_label)?;
+    }
 
-        for i in 0..after_gen_imports.len() {
-            if gen_imports.contains(&after_gen_imports[i]) {
-                generation.imports[i] = String::new();
-            }
-        }
+    let (mut generation, provenance) = resolver.finish();
 
-        generation.imports = generation
-            .imports
-            .into_iter()
-            .filter(|x| *x != String::new())
-            .collect();
-    }
+    generation.apply_overrides();
 
-    Ok(generation)
+    Ok((generation, provenance))
 }
 
 macro_rules! deserialize_legacy {
@@ -400,14 +551,47 @@ pub fn latest_number() -> Result<usize, io::Error> {
     return Ok(latest_num);
 }
 
-// Create a new system generation based on the user generation.
-pub fn commit(msg: &str) -> Result<(), io::Error> {
+/// What happened when committing the user generation.
+pub enum CommitOutcome {
+    /// A new generation was created. (Its number.)
+    Committed(usize),
+    /// The latest generation was rewritten. (Its number.)
+    Amended(usize),
+    /// Nothing changed since the latest generation. (Its number.)
+    Unchanged(usize),
+}
+
+// How a commit should be done.
+#[derive(Default)]
+pub struct CommitOptions {
+    /// Commit even if nothing changed since the latest generation.
+    pub allow_empty: bool,
+    /// Rewrite the latest generation instead of creating a new one.
+    pub amend: bool,
+}
+
+// Create a new system generation based on the user generation. (Without a message, the user writes one in their editor.)
+pub fn commit(msg: Option<&str>, options: &CommitOptions) -> Result<CommitOutcome, io::Error> {
     abort_if_locked();
 
-    let generation_number = match latest_number() {
-        Ok(o) => o,
-        Err(e) => return Err(e),
-    } + 1;
+    let latest = latest_number()?;
+
+    if options.amend {
+        if latest == 0 {
+            error!("There is no generation to amend!");
+            return Err(custom_error("There is no generation to amend!"));
+        }
+
+        if get_built_no_output().ok() == Some(latest) {
+            error!("Generation {latest} is built, so it can't be amended! (Commit a new generation instead.)");
+            return Err(custom_error("Can't amend the built generation!"));
+        }
+    }
+
+    let generation_number = match options.amend {
+        true => latest,
+        false => latest + 1,
+    };
 
     let gen_dir = places::gens().add_str(&generation_number.to_string());
 
@@ -416,6 +600,42 @@ pub fn commit(msg: &str) -> Result<(), io::Error> {
         Err(e) => return Err(e),
     };
 
+    // The generation this one is compared to.
+    let previous_number = match options.amend {
+        true => list_gen_nums()?.into_iter().filter(|x| *x < latest).max(),
+        false => Some(latest).filter(|x| *x > 0),
+    };
+
+    let previous_gen = match previous_number {
+        Some(s) => Some(get_gen_from_usize(s)?),
+        None => None,
+    };
+
+    if !options.allow_empty && !options.amend && previous_gen.as_ref() == Some(&user_gen) {
+        return Ok(CommitOutcome::Unchanged(latest));
+    }
+
+    let msg = match msg {
+        Some(s) => s.to_string(),
+        None => {
+            let initial = match options.amend {
+                true => get_gen_commit_from_usize(latest)?,
+                false => String::new(),
+            };
+
+            message::edit(
+                &initial,
+                previous_number.zip(previous_gen.as_ref()),
+                &user_gen,
+            )?
+        }
+    };
+
+    if msg.trim().is_empty() {
+        error!("Aborting commit, because the commit message is empty!");
+        return Err(custom_error("Empty commit message!"));
+    }
+
     let user_gen_string = match toml::to_string(&user_gen) {
         Ok(o) => o,
         Err(_e) => {
@@ -424,32 +644,55 @@ pub fn commit(msg: &str) -> Result<(), io::Error> {
         }
     };
 
-    match directory::create(&gen_dir) {
-        Ok(_) => info!("Created generation directory."),
-        Err(e) => {
-            error!("Failed to create generation directory!");
-            return Err(e);
-        }
-    };
+    let git_revision = metadata::git_revision(&msg)?;
 
-    let files = vec![
+    let mut files = vec![
         (msg, gen_dir.add_str("commit")),
-        (user_gen_string.as_str(), gen_dir.add_str("gen.toml")),
+        (user_gen_string, gen_dir.add_str("gen.toml")),
     ];
 
-    for i in files.iter() {
-        match file::write(i.0, &i.1) {
-            Ok(_o) => info!("Created file: {}", i.1.basename()),
+    // An amended generation keeps its original metadata, apart from the git revision.
+    if options.amend {
+        if let (Some(mut meta), Some(_)) = (metadata::read(latest)?, git_revision.as_ref()) {
+            meta.git = git_revision;
+
+            files.push((metadata::to_string(&meta)?, gen_dir.add_str("meta.toml")));
+        }
+    } else {
+        let parent = match places::gens().add_str("current").exists() {
+            true => Some(get_current()?),
+            false => None,
+        };
+
+        let mut meta = metadata::Metadata::new(parent)?;
+        meta.git = git_revision;
+
+        files.push((metadata::to_string(&meta)?, gen_dir.add_str("meta.toml")));
+
+        match directory::create(&gen_dir) {
+            Ok(_) => info!("Created generation directory."),
             Err(e) => {
-                error!("Failed to create file: {}", i.1.basename());
+                error!("Failed to create generation directory!");
+                return Err(e);
+            }
+        };
+    }
 
-                match fs_action::delete(&gen_dir) {
-                    Ok(_) => (),
-                    Err(e) => {
-                        error!("Failed to delete generation directory!");
-                        return Err(e);
-                    }
-                };
+    for i in files.iter() {
+        match file::write(&i.0, &i.1) {
+            Ok(_o) => info!("Wrote file: {}", i.1.basename()),
+            Err(e) => {
+                error!("Failed to write file: {}", i.1.basename());
+
+                if !options.amend {
+                    match fs_action::delete(&gen_dir) {
+                        Ok(_) => (),
+                        Err(e) => {
+                            error!("Failed to delete generation directory!");
+                            return Err(e);
+                        }
+                    };
+                }
 
                 return Err(e);
             }
@@ -461,79 +704,364 @@ pub fn commit(msg: &str) -> Result<(), io::Error> {
         Err(e) => return Err(e),
     };
 
-    return Ok(());
+    match options.amend {
+        true => Ok(CommitOutcome::Amended(generation_number)),
+        false => Ok(CommitOutcome::Committed(generation_number)),
+    }
 }
 
+// The order the managers of a generation are used in. Managers can declare 'after' and 'before'
+// in their TOML, and manager_order.toml puts managers at the beginning or the end.
 fn get_order(gen: &Generation) -> Result<Vec<String>, io::Error> {
-    let return_order = {
-        let path = places::base_user().add_str("manager_order.toml");
+    let (nodes, edges) = order_graph(gen)?;
 
-        if path.exists() {
-            info!("Reading order rules from manager_order.toml...");
+    match order::topological_sort(&nodes, &edges) {
+        Ok(o) => Ok(o),
+        Err(cycle) => {
+            error!(
+                "Found a cycle in the manager order! ({})",
+                cycle.join(" -> ")
+            );
+            note!("Check 'after' and 'before' of these managers, and manager_order.toml.");
 
-            let order_obj: ManagerOrder = match toml::from_str(&file::read(&path)?) {
-                Ok(o) => o,
-                Err(e) => {
-                    error!("Failed to deserialize manager_order.toml!");
-                    error!("TOML Error: {e:#?}");
+            Err(custom_error("Found a cycle in the manager order!"))
+        }
+    }
+}
 
-                    return Err(custom_error("Failed to deserialize manager_order.toml!"));
-                }
-            };
+// The managers of a generation, and which of them have to come before which.
+fn order_graph(gen: &Generation) -> Result<(Vec<String>, Vec<order::Edge>), io::Error> {
+    let nodes: Vec<String> = gen.managers.keys().map(|x| x.to_string()).collect();
 
-            let mut order: Vec<String> = order_obj.begin.clone();
+    // (a, b): 'a' has to come before 'b'.
+    let mut edges: Vec<order::Edge> = Vec::new();
 
-            for k in gen.managers.keys() {
-                if order_obj.begin.contains(k) || order_obj.end.contains(k) {
-                    continue;
-                }
+    for n in nodes.iter() {
+        let manager = load_manager(n)?;
+
+        for a in manager.after.iter() {
+            edges.push((a.to_string(), n.to_string()));
+        }
+
+        for b in manager.before.iter() {
+            edges.push((n.to_string(), b.to_string()));
+        }
+    }
+
+    let path = places::base_user().add_str("manager_order.toml");
+
+    if path.exists() {
+        info!("Reading order rules from manager_order.toml...");
 
-                order.push(k.to_string());
+        let order_obj: ManagerOrder = match toml::from_str(&file::read(&path)?) {
+            Ok(o) => o,
+            Err(e) => {
+                error!("Failed to deserialize manager_order.toml!");
+                error!("TOML Error: {e:#?}");
+
+                return Err(custom_error("Failed to deserialize manager_order.toml!"));
             }
+        };
 
-            order.extend(order_obj.end);
+        let mut dup_track: HashMap<String, usize> = HashMap::new();
 
-            let mut dup_track: HashMap<String, usize> = HashMap::new();
+        for o in order_obj.begin.iter().chain(order_obj.end.iter()) {
+            *dup_track.entry(o.to_string()).or_insert(0) += 1;
+        }
 
-            for o in order.iter() {
-                if dup_track.get(o) == None {
-                    dup_track.insert(o.to_string(), 1);
+        for (key, value) in dup_track.into_iter() {
+            if value == 1 {
+                continue;
+            }
 
-                    continue;
-                }
+            warning!("Duplicates in manager_order.toml! (Found {value} of: '{key}')");
+        }
 
-                *dup_track.get_mut(o).unwrap() += 1;
+        // Only the first place a duplicate is listed counts. ('begin' before 'end')
+        let mut begin: Vec<&String> = Vec::new();
+        let mut end: Vec<&String> = Vec::new();
+
+        for o in order_obj.begin.iter() {
+            if nodes.contains(o) && !begin.contains(&o) {
+                begin.push(o);
             }
+        }
 
-            for (key, value) in dup_track.into_iter() {
-                if value == 1 {
-                    continue;
-                }
+        for o in order_obj.end.iter() {
+            if nodes.contains(o) && !begin.contains(&o) && !end.contains(&o) {
+                end.push(o);
+            }
+        }
+
+        // The 'begin' managers go first (in the given order), the 'end' managers go last.
+        for pair in begin.windows(2).chain(end.windows(2)) {
+            edges.push((pair[0].to_string(), pair[1].to_string()));
+        }
 
-                warning!("Duplicates in manager_order.toml! (Found {value} of: '{key}')");
+        for n in nodes.iter() {
+            for b in begin.iter().filter(|_| !begin.contains(&n)) {
+                edges.push((b.to_string(), n.to_string()));
             }
 
-            order
-                .into_iter()
-                .filter(|x| gen.managers.contains_key(x))
-                .collect()
-        } else {
-            gen.managers
-                .keys()
-                .into_iter()
-                .map(|x| x.to_string())
-                .collect()
+            for e in end.iter().filter(|_| !end.contains(&n)) {
+                edges.push((n.to_string(), e.to_string()));
+            }
+        }
+    }
+
+    Ok((nodes, edges))
+}
+
+// A single manager action that a build has to perform.
+pub struct BuildStep {
+    pub manager: String,
+    pub mode: HistoryMode,
+    pub items: Vec<Item>,
+}
+
+impl BuildStep {
+    // The ID this step is recorded under in the build journal.
+    pub fn id(&self) -> String {
+        let action = match self.mode {
+            HistoryMode::Remove => "remove",
+            HistoryMode::Add | HistoryMode::Change => "add",
+        };
+
+        format!("{}:{}", self.manager, action)
+    }
+}
+
+// Everything a build is going to do, in order.
+pub struct BuildPlan {
+    pub steps: Vec<BuildStep>,
+    /// None when building for the first time.
+    pub summary: Option<HashMap<String, Vec<History>>>,
+}
+
+// Work out which items each manager has to remove and add to get from 'built_gen' to 'curr_gen'.
+pub fn plan_build(
+    curr_gen: &Generation,
+    built_gen: Option<&Generation>,
+) -> Result<BuildPlan, io::Error> {
+    let mut steps: Vec<BuildStep> = Vec::new();
+
+    let built_gen = match built_gen {
+        Some(s) => s,
+        None => {
+            for i in get_order(curr_gen)?.iter() {
+                let curr_items = curr_gen.managers.get(i).unwrap();
+
+                steps.push(BuildStep {
+                    manager: i.to_string(),
+                    mode: HistoryMode::Add,
+                    items: curr_items.items.clone(),
+                });
+            }
+
+            return Ok(BuildPlan {
+                steps,
+                summary: None,
+            });
         }
     };
 
-    Ok(return_order)
+    let mut summary_entries: HashMap<String, Vec<History>> = HashMap::new();
+
+    // Remove old items, add new items,
+    for i in get_order(curr_gen)?.iter() {
+        let curr_items = curr_gen.managers.get(i).unwrap();
+
+        match built_gen.managers.get(i) {
+            Some(built_items) => {
+                let diffs = history(&built_items.items, &curr_items.items);
+
+                let mut to_install: Vec<Item> = Vec::new();
+                let mut to_remove: Vec<Item> = Vec::new();
+
+                // Changed items get installed again, at their new version.
+                for j in diffs.iter() {
+                    match j.mode {
+                        HistoryMode::Add | HistoryMode::Change => to_install.push(j.item.clone()),
+                        HistoryMode::Remove => to_remove.push(j.item.clone()),
+                    };
+                }
+
+                steps.push(BuildStep {
+                    manager: i.to_string(),
+                    mode: HistoryMode::Remove,
+                    items: to_remove,
+                });
+                steps.push(BuildStep {
+                    manager: i.to_string(),
+                    mode: HistoryMode::Add,
+                    items: to_install,
+                });
+
+                summary_entries.insert(i.to_string(), diffs);
+            }
+            None => {
+                steps.push(BuildStep {
+                    manager: i.to_string(),
+                    mode: HistoryMode::Add,
+                    items: curr_items.items.clone(),
+                });
+
+                summary_entries.insert(
+                    i.to_string(),
+                    curr_items
+                        .items
+                        .iter()
+                        .map(|x| History::new(HistoryMode::Add, x))
+                        .collect(),
+                );
+            }
+        }
+    }
+
+    // Remove items from managers that were removed from the generation.
+    for i in get_order(built_gen)?.iter() {
+        let built_items = built_gen.managers.get(i).unwrap();
+
+        if curr_gen.managers.contains_key(i) {
+            continue;
+        }
+
+        steps.push(BuildStep {
+            manager: i.to_string(),
+            mode: HistoryMode::Remove,
+            items: built_items.items.clone(),
+        });
+
+        summary_entries.insert(
+            i.to_string(),
+            built_items
+                .items
+                .iter()
+                .map(|x| History::new(HistoryMode::Remove, x))
+                .collect(),
+        );
+    }
+
+    Ok(BuildPlan {
+        steps,
+        summary: Some(summary_entries),
+    })
+}
+
+// Pick the journal to build with. (Resume the interrupted build, or start a new one.)
+fn open_journal(
+    resume: bool,
+    current_num: usize,
+    built_num: Option<usize>,
+) -> Result<journal::Journal, io::Error> {
+    match (journal::read()?, resume) {
+        (Some(j), true) => {
+            if j.generation != current_num || j.from != built_num {
+                error!(
+                    "The interrupted build was for generation {}, but 'current' or 'built' has changed since!",
+                    j.generation
+                );
+                note!("Run the build again without '--resume' to start over.");
+
+                return Err(custom_error("Build journal does not match!"));
+            }
+
+            info!(
+                "Resuming build of generation {}... ({} steps already done)",
+                j.generation,
+                j.completed.len()
+            );
+
+            Ok(j)
+        }
+        (Some(j), false) => {
+            warning!(
+                "Found the journal of an interrupted build of generation {}, starting over...",
+                j.generation
+            );
+            note!("Use '--resume' to continue an interrupted build instead.");
+
+            Ok(journal::Journal::new(current_num, built_num))
+        }
+        (None, true) => {
+            error!("There is no interrupted build to resume!");
+
+            Err(custom_error("No build journal found!"))
+        }
+        (None, false) => Ok(journal::Journal::new(current_num, built_num)),
+    }
+}
+
+// Print what a build is going to do, without doing any of it.
+fn print_plan(plan: &BuildPlan, journal: &journal::Journal) -> Result<(), io::Error> {
+    let mut nothing_to_do = true;
+
+    println!();
+
+    for step in plan.steps.iter() {
+        if step.items.is_empty() {
+            continue;
+        }
+
+        nothing_to_do = false;
+
+        let step_id = step.id();
+
+        if journal.is_done(&step_id) {
+            note!("Skipping '{step_id}', it was already done.");
+
+            continue;
+        }
+
+        let man = load_manager(&step.manager)?;
+
+        let commands = match step.mode {
+            HistoryMode::Remove => man.remove_commands(&step.items),
+            HistoryMode::Add | HistoryMode::Change => man.add_commands(&step.items)?,
+        };
+
+        info!("{step_id}:");
+
+        library::print_history(
+            &step
+                .items
+                .iter()
+                .map(|x| History::new(step.mode, x))
+                .collect(),
+        );
+
+        for command in commands.iter() {
+            println!("{} {}", "$:".bright_cyan().bold(), command.bright_magenta());
+        }
+
+        println!();
+    }
+
+    if nothing_to_do {
+        info!("Nothing to do, the generation is already built!");
+    }
+
+    Ok(())
+}
+
+// How a build should be done.
+#[derive(Default)]
+pub struct BuildOptions {
+    /// Continue an interrupted build.
+    pub resume: bool,
+    /// Only print the plan, don't execute anything.
+    pub dry_run: bool,
+    /// How many managers can run at the same time. (0 and 1 both mean one after another.)
+    pub jobs: usize,
 }
 
 // Build the 'current' system generation.
-pub fn build() -> Result<(), io::Error> {
+pub fn build(options: &BuildOptions) -> Result<(), io::Error> {
     abort_if_locked();
 
-    run_hook_and_return_if_err!("pre_build");
+    if !options.dry_run {
+        run_hook_and_return_if_err!("pre_build");
+    }
 
     let current_num = match get_current() {
         Ok(o) => o,
@@ -545,113 +1073,66 @@ pub fn build() -> Result<(), io::Error> {
         Err(e) => return Err(e),
     };
 
-    match file::read(&places::gens().add_str("built")) {
-        Ok(o) => {
-            let built_gen = read_to_gen(&places::gens().add_str(o.trim()).add_str("gen.toml"))?;
-
-            let mut summary_entries: HashMap<String, Vec<History>> = HashMap::new();
+    let built_num = get_built_no_output().ok();
 
-            let curr_order: Vec<String> = get_order(&curr_gen)?;
+    let built_gen = match built_num {
+        Some(s) => Some(get_gen_from_usize(s)?),
+        None => None,
+    };
 
-            // Remove old items, add new items,
-            for i in curr_order.iter() {
-                let man = load_manager(i)?;
+    let plan = plan_build(&curr_gen, built_gen.as_ref())?;
 
-                let curr_items = curr_gen.managers.get(i).unwrap();
+    let mut journal = open_journal(options.resume, current_num, built_num)?;
 
-                match built_gen.managers.get(i) {
-                    Some(built_items) => {
-                        let diffs = history(&built_items.items, &curr_items.items);
+    if options.dry_run {
+        info!("Plan for building generation {current_num}: (Nothing will be executed.)");
 
-                        let mut to_install: Vec<String> = Vec::new();
-                        let mut to_remove: Vec<String> = Vec::new();
+        return print_plan(&plan, &journal);
+    }
 
-                        for j in diffs.iter() {
-                            match j.mode {
-                                HistoryMode::Add => to_install.push(j.line.to_string()),
-                                HistoryMode::Remove => to_remove.push(j.line.to_string()),
-                            };
-                        }
+    journal::write(&journal)?;
 
-                        man.remove(&to_remove)?;
-                        man.add(&to_install)?;
+    if options.jobs > 1 {
+        let (_, mut edges) = order_graph(&curr_gen)?;
 
-                        summary_entries.insert(i.to_string(), diffs);
-                    }
-                    None => {
-                        man.add(&curr_items.items)?;
-
-                        summary_entries.insert(
-                            i.to_string(),
-                            curr_items
-                                .items
-                                .iter()
-                                .map(|x| History {
-                                    mode: HistoryMode::Add,
-                                    line: x.to_string(),
-                                })
-                                .collect(),
-                        );
-                    }
+        // Managers that were removed from the generation are cleaned up last, like in a sequential build.
+        if let Some(ref built_gen) = built_gen {
+            for removed in built_gen
+                .managers
+                .keys()
+                .filter(|x| !curr_gen.managers.contains_key(*x))
+            {
+                for m in curr_gen.managers.keys() {
+                    edges.push((m.to_string(), removed.to_string()));
                 }
             }
+        }
 
-            let built_order: Vec<String> = get_order(&built_gen)?;
-
-            // Remove items from managers that were removed from the generation.
-            for i in built_order.iter() {
-                let built_items = built_gen.managers.get(i).unwrap();
-
-                match curr_gen.managers.get(i) {
-                    Some(_) => (),
-                    None => {
-                        let man = load_manager(i)?;
-
-                        man.remove(&built_items.items)?;
-
-                        summary_entries.insert(
-                            i.to_string(),
-                            built_items
-                                .items
-                                .iter()
-                                .map(|x| History {
-                                    mode: HistoryMode::Remove,
-                                    line: x.to_string(),
-                                })
-                                .collect(),
-                        );
-                    }
-                };
-            }
+        parallel::run_steps(&plan.steps, &edges, &mut journal, options.jobs)?;
+    } else {
+        for step in plan.steps.iter() {
+            let step_id = step.id();
 
-            println!("");
-            println!("");
-            println!("");
+            if journal.is_done(&step_id) {
+                note!("Skipping '{step_id}', it was already done.");
 
-            info!("#################");
-            info!("#    SUMMARY    #");
-            info!("#################");
+                continue;
+            }
 
-            println!("");
+            let man = load_manager(&step.manager)?;
 
-            library::print_history_gen(&summary_entries);
+            match step.mode {
+                HistoryMode::Remove => man.remove(&step.items)?,
+                HistoryMode::Add | HistoryMode::Change => man.add(&step.items)?,
+            };
 
-            println!("");
-            println!("");
+            journal.mark_done(&step_id)?;
         }
-        Err(_) => {
-            let curr_order = get_order(&curr_gen)?;
-
-            for i in curr_order.iter() {
-                let curr_items = curr_gen.managers.get(i).unwrap();
-
-                let man = load_manager(i)?;
-
-                man.add(&curr_items.items)?;
-            }
+    }
 
-            note!("There is no summary. (First time building.)");
-        }
+    match plan.summary {
+        Some(summary_entries) => print_summary(&summary_entries),
+        None => note!("There is no summary. (First time building.)"),
     };
 
     match set_built(current_num, true) {
@@ -659,6 +1140,74 @@ pub fn build() -> Result<(), io::Error> {
         Err(e) => return Err(e),
     };
 
+    metadata::write_built_at(current_num)?;
+
+    journal::clear()?;
+
+    run_hook_and_return_if_err!("post_build");
+
+    Ok(())
+}
+
+// Print the summary of what a build (or repair) did.
+fn print_summary(summary_entries: &HashMap<String, Vec<History>>) {
+    println!();
+    println!();
+    println!();
+
+    info!("#################");
+    info!("#    SUMMARY    #");
+    info!("#################");
+
+    println!();
+
+    library::print_history_gen(summary_entries);
+
+    println!();
+    println!();
+}
+
+// Reinstall the items of the built generation that aren't installed anymore.
+pub fn repair() -> Result<(), io::Error> {
+    abort_if_locked();
+
+    run_hook_and_return_if_err!("pre_build");
+
+    let built_gen = built_gen()?;
+
+    let missing = crate::management::get_missing(&built_gen)?.missing;
+
+    let mut summary_entries: HashMap<String, Vec<History>> = HashMap::new();
+
+    for i in get_order(&built_gen)?.iter() {
+        let man_missing = match missing.iter().find(|x| x.0 == *i) {
+            Some(s) => &s.1,
+            None => continue,
+        };
+
+        if man_missing.is_empty() {
+            continue;
+        }
+
+        let man = load_manager(i)?;
+
+        man.add(man_missing)?;
+
+        summary_entries.insert(
+            i.to_string(),
+            man_missing
+                .iter()
+                .map(|x| History::new(HistoryMode::Add, x))
+                .collect(),
+        );
+    }
+
+    if summary_entries.is_empty() {
+        info!("Nothing to repair, all items of the built generation are installed!");
+    } else {
+        print_summary(&summary_entries);
+    }
+
     run_hook_and_return_if_err!("post_build");
 
     Ok(())
@@ -769,12 +1318,25 @@ pub fn set_built(to: usize, verbose: bool) -> Result<(), io::Error> {
     };
 }
 
-// Get the 'current' generation number.
+// Get the 'current' generation number. (With output.)
 pub fn get_current() -> Result<usize, io::Error> {
+    get_current_core(true)
+}
+
+// Get the 'current' generation number. (Without output.)
+pub fn get_current_no_output() -> Result<usize, io::Error> {
+    get_current_core(false)
+}
+
+// Get the 'current' generation number. (CORE)
+pub fn get_current_core(output: bool) -> Result<usize, io::Error> {
     let contents = match file::read(&places::gens().add_str("current")) {
         Ok(o) => o,
         Err(e) => {
-            error!("Failed to read 'current' file!");
+            if output {
+                error!("Failed to read 'current' file!");
+            }
+
             return Err(e);
         }
     };
@@ -782,9 +1344,12 @@ pub fn get_current() -> Result<usize, io::Error> {
     let generation: usize = match contents.trim().parse() {
         Ok(o) => o,
         Err(_e) => {
-            error!(
-                "Failed to parse number from 'current' file! (Maybe 'current' file is corrupted?)"
-            );
+            if output {
+                error!(
+                    "Failed to parse number from 'current' file! (Maybe 'current' file is corrupted?)"
+                );
+            }
+
             return Err(custom_error(
                 "Failed to parse number out of 'current' file!",
             ));
@@ -833,6 +1398,17 @@ pub fn get_built_core(output: bool) -> Result<usize, io::Error> {
     return Ok(generation);
 }
 
+// Get the built generation.
+pub fn built_gen() -> Result<Generation, io::Error> {
+    if !been_built() {
+        error!("No generation has been built yet!");
+
+        return Err(custom_error("No generation has been built yet!"));
+    }
+
+    get_gen_from_usize(get_built()?)
+}
+
 // Has a generation been built yet?
 pub fn been_built() -> bool {
     return places::gens().add_str("built").exists();
@@ -888,6 +1464,14 @@ pub fn delete(generation: usize, verbose: bool) -> Result<(), io::Error> {
         }
     }
 
+    if is_pinned(generation) {
+        warning!(
+            "Could not delete generation {}, because it is pinned, and is protected!",
+            generation
+        );
+        return Ok(());
+    }
+
     if match exists(generation) {
         Ok(o) => o,
         Err(e) => return Err(e),
@@ -912,6 +1496,81 @@ pub fn delete(generation: usize, verbose: bool) -> Result<(), io::Error> {
     return Ok(());
 }
 
+fn pin_marker(generation: usize) -> Path {
+    places::gens()
+        .add_str(&generation.to_string())
+        .add_str("pinned")
+}
+
+// Is the generation pinned? (Pinned generations are never deleted.)
+pub fn is_pinned(generation: usize) -> bool {
+    pin_marker(generation).exists()
+}
+
+// Protect a generation from being deleted.
+pub fn pin(generation: usize, verbose: bool) -> Result<(), io::Error> {
+    abort_if_locked();
+
+    if !gen_exists(generation) {
+        error!("Generation {} does not exist!", generation);
+        return Err(custom_error("Generation does not exist!"));
+    }
+
+    if is_pinned(generation) {
+        if verbose {
+            info!("Generation {} is already pinned.", generation);
+        }
+
+        return Ok(());
+    }
+
+    match file::write("", &pin_marker(generation)) {
+        Ok(_) => {
+            if verbose {
+                info!("Pinned generation: {}", generation);
+            }
+
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to create pin marker for generation {}!", generation);
+            Err(e)
+        }
+    }
+}
+
+// Stop protecting a generation from being deleted.
+pub fn unpin(generation: usize, verbose: bool) -> Result<(), io::Error> {
+    abort_if_locked();
+
+    if !gen_exists(generation) {
+        error!("Generation {} does not exist!", generation);
+        return Err(custom_error("Generation does not exist!"));
+    }
+
+    if !is_pinned(generation) {
+        if verbose {
+            info!("Generation {} is not pinned.", generation);
+        }
+
+        return Ok(());
+    }
+
+    match fs_action::delete(&pin_marker(generation)) {
+        Ok(_) => {
+            if verbose {
+                info!("Unpinned generation: {}", generation);
+            }
+
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to delete pin marker of generation {}!", generation);
+            Err(e)
+        }
+    }
+}
+
 // Move a generation to another spot. (Number -> Number)
 pub fn move_gen(from: usize, to: usize, verbose: bool) -> Result<(), io::Error> {
     abort_if_locked();
@@ -959,10 +1618,7 @@ pub fn list_gen_nums() -> Result<Vec<usize>, io::Error> {
     let mut gen_nums: Vec<usize> = Vec::new();
 
     for i in gen_list.iter() {
-        gen_nums.push(match usize_from_gen_name(i.0.as_str()) {
-            Ok(o) => o,
-            Err(e) => return Err(e),
-        });
+        gen_nums.push(i.number);
     }
 
     return Ok(gen_nums);
@@ -1007,18 +1663,35 @@ pub fn is_built(generation: usize) -> Result<bool, io::Error> {
     }
 }
 
+// Information about a generation, as shown by the list command.
+#[derive(Serialize, Clone, Debug)]
+pub struct GenerationInfo {
+    /// The generation number.
+    pub number: usize,
+    /// The commit message.
+    pub commit: String,
+    /// Is this the 'current' generation?
+    pub current: bool,
+    /// Is this the built generation?
+    pub built: bool,
+    /// Is this generation pinned? (Protected from deletion.)
+    pub pinned: bool,
+    /// When the generation was created. (Unix timestamp, in seconds. None for generations without metadata.)
+    pub created: Option<u64>,
+}
+
 // List all generations. (NORMAL)
-pub fn list() -> Result<Vec<(String, String, bool, bool)>, io::Error> {
+pub fn list() -> Result<Vec<GenerationInfo>, io::Error> {
     return list_core(true);
 }
 
 // List all generations. (ISOLATED MODE | For avoiding errors with called un-needed functions!)
-pub fn list_with_no_calls() -> Result<Vec<(String, String, bool, bool)>, io::Error> {
+pub fn list_with_no_calls() -> Result<Vec<GenerationInfo>, io::Error> {
     return list_core(false);
 }
 
 // List all generations. (CORE)
-fn list_core(calls: bool) -> Result<Vec<(String, String, bool, bool)>, io::Error> {
+fn list_core(calls: bool) -> Result<Vec<GenerationInfo>, io::Error> {
     let gen_listed = match directory::list_items(&places::gens()) {
         Ok(o) => o,
         Err(e) => {
@@ -1043,10 +1716,10 @@ fn list_core(calls: bool) -> Result<Vec<(String, String, bool, bool)>, io::Error
         };
     }
 
-    let mut gens_with_info: Vec<(String, String, bool, bool)> = Vec::new();
+    let mut gens_with_info: Vec<GenerationInfo> = Vec::new();
 
     for i in generations.iter() {
-        let generation_name = i.basename();
+        let generation_number = usize_from_gen_name(&i.basename())?;
         let commit_msg = file::read(&i.add_str("commit"))
             .unwrap_or(String::from("<< COMMIT MESSAGE MISSING >>"));
 
@@ -1067,25 +1740,114 @@ fn list_core(calls: bool) -> Result<Vec<(String, String, bool, bool)>, io::Error
             built_number = 0;
         }
 
-        let is_current: bool;
-        let is_built: bool;
+        let created = match calls {
+            true => metadata::read(generation_number)?.map(|x| x.created),
+            false => None,
+        };
 
-        if generation_name == current_number.to_string() {
-            is_current = true;
-        } else {
-            is_current = false;
-        }
+        gens_with_info.push(GenerationInfo {
+            number: generation_number,
+            commit: commit_msg,
+            current: generation_number == current_number,
+            built: generation_number == built_number,
+            pinned: i.add_str("pinned").exists(),
+            created,
+        });
+    }
 
-        if generation_name == built_number.to_string() {
-            is_built = true;
-        } else {
-            is_built = false;
-        }
+    return Ok(gens_with_info);
+}
+
+/// How the user config, the 'current' generation and the built generation relate to each other.
+pub struct Status {
+    /// The 'current' generation. (None if there are no generations yet.)
+    pub current: Option<usize>,
+    /// The built generation. (None if nothing was built yet.)
+    pub built: Option<usize>,
+    /// Changes in the user config that aren't committed yet. (Only managers with changes.)
+    pub uncommitted: HashMap<String, Vec<History>>,
+}
 
-        gens_with_info.push((generation_name, commit_msg, is_current, is_built));
+impl Status {
+    pub fn has_uncommitted(&self) -> bool {
+        !self.uncommitted.is_empty()
     }
 
-    return Ok(gens_with_info);
+    pub fn current_is_built(&self) -> bool {
+        self.current == self.built
+    }
+}
+
+// Compare the user config to the 'current' generation, and the 'current' generation to the built one.
+pub fn status() -> Result<Status, io::Error> {
+    let current = match places::gens().add_str("current").exists() {
+        true => Some(get_current()?),
+        false => None,
+    };
+
+    let built = get_built_no_output().ok();
+
+    let current_gen = match current {
+        Some(s) => get_gen_from_usize(s)?,
+        None => Generation::default(),
+    };
+
+    let uncommitted = history_gen(&current_gen, &gen(ConfigSide::User)?)
+        .into_iter()
+        .filter(|(_, v)| !v.is_empty())
+        .collect();
+
+    Ok(Status {
+        current,
+        built,
+        uncommitted,
+    })
+}
+
+// Print out the status.
+pub fn status_print(status: &Status) -> Result<(), io::Error> {
+    match status.current {
+        Some(s) => generic!(
+            "Current generation: {s} ({})",
+            message::first_line(&get_gen_commit_from_usize(s)?)
+        ),
+        None => generic!("Current generation: none"),
+    };
+
+    match status.built {
+        Some(s) => generic!(
+            "Built generation: {s} ({})",
+            message::first_line(&get_gen_commit_from_usize(s)?)
+        ),
+        None => generic!("Built generation: none"),
+    };
+
+    println!();
+
+    if !status.current_is_built() {
+        warning!("The 'current' generation isn't built! (Build it with: rebos gen current build)");
+    }
+
+    if status.has_uncommitted() {
+        warning!("The user config has uncommitted changes! (Commit them with: rebos gen commit)");
+
+        println!();
+
+        let mut managers: Vec<&String> = status.uncommitted.keys().collect();
+        managers.sort();
+
+        for m in managers {
+            info!("{m}:");
+
+            print_history(&status.uncommitted[m]);
+
+            println!();
+        }
+    } else {
+        success!("Nothing to commit, the user config matches the 'current' generation.");
+    }
+
+    Ok(())
 }
 
 // Print out the list of generations.
@@ -1095,25 +1857,21 @@ pub fn list_print() -> Result<(), io::Error> {
         Err(e) => return Err(e),
     };
 
-    let list_items_sorted = match sort_list_vector(&list_items) {
-        Ok(o) => o,
-        Err(e) => return Err(e),
-    };
+    let list_items_sorted = sort_list_vector(&list_items);
 
     let mut max_digits: usize = 0;
 
     if list_items_sorted.len() > 0 {
         max_digits = list_items_sorted[list_items_sorted.len() - 1]
-            .0
+            .number
             .to_string()
-            .trim()
             .len();
     }
 
     for i in list_items_sorted.iter() {
         let mut misc_text = String::new();
 
-        if i.2 {
+        if i.current {
             misc_text.push_str(
                 format!(
                     " {}{}{}",
@@ -1125,7 +1883,7 @@ pub fn list_print() -> Result<(), io::Error> {
             );
         }
 
-        if i.3 {
+        if i.built {
             misc_text.push_str(
                 format!(
                     " {}{}{}",
@@ -1137,92 +1895,64 @@ pub fn list_print() -> Result<(), io::Error> {
             );
         }
 
+        if i.pinned {
+            misc_text.push_str(
+                format!(
+                    " {}{}{}",
+                    "[".bright_black().bold(),
+                    "PINNED".bright_blue().bold(),
+                    "]".bright_black().bold()
+                )
+                .as_str(),
+            );
+        }
+
+        let name = i.number.to_string();
+
         let mut tabbed = String::new();
 
-        for _j in 0..(max_digits - i.0.trim().len()) {
+        for _j in 0..(max_digits - name.len()) {
             tabbed.push_str(" ");
         }
 
-        generic!("{}{} ... ({}){}", tabbed, i.0, i.1, misc_text);
-    }
-
-    return Ok(());
-}
-
-// Get only list vector generation names.
-fn get_list_vector_names(list_vec: &Vec<(String, String, bool, bool)>) -> Vec<String> {
-    let mut new_vec: Vec<String> = Vec::new();
+        let date = match i.created {
+            Some(s) => format!("{} ", format_timestamp(s).bright_black()),
+            None => String::new(),
+        };
 
-    for i in list_vec.iter() {
-        new_vec.push(i.0.to_string());
+        generic!(
+            "{}{} ... {}({}){}",
+            tabbed,
+            name,
+            date,
+            message::first_line(&i.commit),
+            misc_text
+        );
     }
 
-    return new_vec;
+    return Ok(());
 }
 
 // Sort list vector.
-fn sort_list_vector(
-    list_vec: &Vec<(String, String, bool, bool)>,
-) -> Result<Vec<(String, String, bool, bool)>, io::Error> {
-    if list_vec.len() == 0 {
-        return Ok(list_vec.clone());
-    }
-
-    let list_vec_names = get_list_vector_names(&list_vec);
+pub fn sort_list_vector(list_vec: &[GenerationInfo]) -> Vec<GenerationInfo> {
+    let mut new_vec = list_vec.to_vec();
 
-    let mut list_vec_nums: Vec<usize> = Vec::new();
-
-    for i in list_vec_names.iter() {
-        list_vec_nums.push(match usize_from_gen_name(i) {
-            Ok(o) => o,
-            Err(e) => return Err(e),
-        });
-    }
-
-    list_vec_nums.sort();
-
-    let mut new_vec: Vec<(String, String, bool, bool)> = Vec::new();
-
-    for i in list_vec_nums.iter() {
-        for j in list_vec.iter() {
-            let j_num: usize = match usize_from_gen_name(j.0.as_str()) {
-                Ok(o) => o,
-                Err(e) => return Err(e),
-            };
+    new_vec.sort_by_key(|x| x.number);
 
-            if &j_num == i {
-                new_vec.push(j.clone());
-                break;
-            }
-        }
-    }
-
-    return Ok(new_vec);
+    new_vec
 }
 
 // Get oldest generation name.
 pub fn get_oldest() -> Result<usize, io::Error> {
-    let gen_names = get_list_vector_names(&match sort_list_vector(&match list_with_no_calls() {
-        Ok(o) => o,
-        Err(e) => return Err(e),
-    }) {
-        Ok(o) => o,
-        Err(e) => return Err(e),
-    });
+    let gen_list = list_with_no_calls()?;
 
-    if gen_names.len() == 0 {
-        error!("Tried to call generation::get_oldest(), when there are no generations!");
-        return Err(custom_error("Not enough generations!"));
+    match gen_list.iter().map(|x| x.number).min() {
+        Some(s) => Ok(s),
+        None => {
+            error!("Tried to call generation::get_oldest(), when there are no generations!");
+            Err(custom_error("Not enough generations!"))
+        }
     }
-
-    let oldest_name = gen_names[0].to_string();
-
-    let oldest_number: usize = match usize_from_gen_name(oldest_name.as_str()) {
-        Ok(o) => o,
-        Err(e) => return Err(e),
-    };
-
-    return Ok(oldest_number);
 }
 
 // Get the 'current' generation TOML file.
@@ -1236,3 +1966,51 @@ pub fn current_gen() -> Result<Path, io::Error> {
         .add_str(&current.to_string())
         .add_str("gen.toml"));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facts() -> system::HostFacts {
+        system::HostFacts {
+            distros: vec![String::from("ubuntu"), String::from("debian")],
+            arch: String::from("x86_64"),
+        }
+    }
+
+    #[test]
+    fn when_matches_every_condition() {
+        let when = |distro: Option<&str>, arch: Option<&str>| When {
+            distro: distro.map(|x| x.to_string()),
+            arch: arch.map(|x| x.to_string()),
+            ..Default::default()
+        };
+
+        assert!(when(None, None).matches(&facts()));
+        // 'ID_LIKE' counts, and the distro isn't case sensitive.
+        assert!(when(Some("Debian"), None).matches(&facts()));
+        assert!(when(Some("ubuntu"), Some("x86_64")).matches(&facts()));
+        assert!(!when(Some("fedora"), None).matches(&facts()));
+        assert!(!when(Some("ubuntu"), Some("aarch64")).matches(&facts()));
+    }
+
+    #[test]
+    fn when_matches_environment_variables() {
+        let when = |env: &str| When {
+            env: Some(env.to_string()),
+            ..Default::default()
+        };
+
+        std::env::set_var("REBOS_TEST_WHEN_SET", "laptop");
+        std::env::set_var("REBOS_TEST_WHEN_EMPTY", "");
+        std::env::remove_var("REBOS_TEST_WHEN_UNSET");
+
+        assert!(when("REBOS_TEST_WHEN_SET").matches(&facts()));
+        assert!(when("REBOS_TEST_WHEN_SET=laptop").matches(&facts()));
+        assert!(!when("REBOS_TEST_WHEN_SET=desktop").matches(&facts()));
+        // Set, but empty, doesn't count without a value.
+        assert!(!when("REBOS_TEST_WHEN_EMPTY").matches(&facts()));
+        assert!(when("REBOS_TEST_WHEN_EMPTY=").matches(&facts()));
+        assert!(!when("REBOS_TEST_WHEN_UNSET").matches(&facts()));
+    }
+}
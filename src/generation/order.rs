@@ -0,0 +1,171 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+/// (a, b): 'a' has to come before 'b'.
+pub type Edge = (String, String);
+
+// Sort nodes so that for every edge (a, b), 'a' comes before 'b'. Nodes that could go in any
+// order are sorted alphabetically, so the result is the same every time.
+//
+// Returns a cycle (starting and ending with the same node) if there is no such order.
+pub fn topological_sort(nodes: &[String], edges: &[Edge]) -> Result<Vec<String>, Vec<String>> {
+    let mut successors: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    let mut in_degree: BTreeMap<&str, usize> = BTreeMap::new();
+
+    for n in nodes.iter() {
+        successors.entry(n).or_default();
+        in_degree.entry(n).or_insert(0);
+    }
+
+    for (a, b) in edges.iter() {
+        if !in_degree.contains_key(a.as_str()) || !in_degree.contains_key(b.as_str()) {
+            continue;
+        }
+
+        if successors.get_mut(a.as_str()).unwrap().insert(b) {
+            *in_degree.get_mut(b.as_str()).unwrap() += 1;
+        }
+    }
+
+    let mut ready: BTreeSet<&str> = in_degree
+        .iter()
+        .filter(|(_, d)| **d == 0)
+        .map(|(n, _)| *n)
+        .collect();
+
+    let mut sorted: Vec<String> = Vec::new();
+
+    while let Some(n) = ready.pop_first() {
+        sorted.push(n.to_string());
+
+        for s in successors[n].iter() {
+            let degree = in_degree.get_mut(s).unwrap();
+            *degree -= 1;
+
+            if *degree == 0 {
+                ready.insert(s);
+            }
+        }
+    }
+
+    if sorted.len() == in_degree.len() {
+        return Ok(sorted);
+    }
+
+    // Every node that is left is on a cycle, or comes after one. Walking backwards along
+    // unsorted predecessors has to run into a cycle eventually.
+    let left: BTreeSet<&str> = in_degree
+        .keys()
+        .copied()
+        .filter(|x| !sorted.iter().any(|s| s == x))
+        .collect();
+
+    let predecessor = |node: &str| -> &str {
+        successors
+            .iter()
+            .find(|(p, s)| left.contains(*p) && s.contains(node))
+            .map(|(p, _)| *p)
+            .unwrap()
+    };
+
+    let mut walk: Vec<&str> = vec![left.first().unwrap()];
+
+    loop {
+        let p = predecessor(walk.last().unwrap());
+
+        if let Some(start) = walk.iter().position(|x| *x == p) {
+            let mut cycle: Vec<String> =
+                walk[start..].iter().rev().map(|x| x.to_string()).collect();
+            cycle.push(cycle[0].clone());
+
+            return Err(cycle);
+        }
+
+        walk.push(p);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(x: &[&str]) -> Vec<String> {
+        x.iter().map(|x| x.to_string()).collect()
+    }
+
+    fn edges(x: &[(&str, &str)]) -> Vec<Edge> {
+        x.iter()
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn ties_are_alphabetical() {
+        let nodes = strings(&["c", "a", "d", "b"]);
+
+        assert_eq!(
+            topological_sort(&nodes, &[]),
+            Ok(strings(&["a", "b", "c", "d"]))
+        );
+        assert_eq!(
+            topological_sort(&nodes, &edges(&[("d", "a")])),
+            Ok(strings(&["b", "c", "d", "a"]))
+        );
+    }
+
+    #[test]
+    fn edges_are_followed() {
+        let nodes = strings(&["a", "b", "c"]);
+
+        assert_eq!(
+            topological_sort(&nodes, &edges(&[("c", "b"), ("b", "a"), ("c", "a")])),
+            Ok(strings(&["c", "b", "a"]))
+        );
+    }
+
+    #[test]
+    fn edges_to_unknown_nodes_are_ignored() {
+        let nodes = strings(&["a", "b"]);
+
+        assert_eq!(
+            topological_sort(
+                &nodes,
+                &edges(&[("b", "missing"), ("missing", "a"), ("b", "a")])
+            ),
+            Ok(strings(&["b", "a"]))
+        );
+    }
+
+    #[test]
+    fn simple_cycle() {
+        let nodes = strings(&["a", "b", "c", "d"]);
+
+        // 'd' comes after the cycle, but isn't on it.
+        let cycle = topological_sort(
+            &nodes,
+            &edges(&[("a", "b"), ("b", "c"), ("c", "a"), ("c", "d")]),
+        );
+
+        assert_eq!(cycle, Err(strings(&["b", "c", "a", "b"])));
+    }
+
+    #[test]
+    fn cycle_found_from_a_node_after_it() {
+        let nodes = strings(&["a", "b", "c"]);
+
+        // The walk starts at 'a', which only comes after the cycle.
+        assert_eq!(
+            topological_sort(&nodes, &edges(&[("b", "c"), ("c", "b"), ("c", "a")])),
+            Err(strings(&["b", "c", "b"]))
+        );
+    }
+
+    #[test]
+    fn self_loop() {
+        let nodes = strings(&["a", "b"]);
+
+        assert_eq!(
+            topological_sort(&nodes, &edges(&[("b", "b")])),
+            Err(strings(&["b", "b"]))
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use piglog::prelude::*;
+use piglog::*;
+use std::io;
+use std::sync::atomic::AtomicBool;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use super::journal::Journal;
+use super::order::Edge;
+use super::BuildStep;
+use crate::job::{self, Job};
+use crate::library::*;
+use crate::management::load_manager;
+
+// Run the steps of a build with every manager as its own job, running up to 'jobs' at the same
+// time. A job starts once the jobs of the managers it has to come after are done. (See 'edges'.)
+//
+// On the first failure the other jobs are cancelled, and no new ones are started.
+pub fn run_steps(
+    steps: &[BuildStep],
+    edges: &[Edge],
+    journal: &mut Journal,
+    jobs: usize,
+) -> Result<(), io::Error> {
+    // Manager -> its steps, in the order of the plan.
+    let mut managers: Vec<(&str, Vec<&BuildStep>)> = Vec::new();
+
+    for step in steps.iter() {
+        match managers.iter_mut().find(|x| x.0 == step.manager) {
+            Some(s) => s.1.push(step),
+            None => managers.push((&step.manager, vec![step])),
+        };
+    }
+
+    let journal = Mutex::new(journal);
+
+    schedule(&managers, edges, jobs, |man_steps, job| {
+        run_job(man_steps, &journal, job)
+    })
+}
+
+// Run every manager's steps with 'run', as its own job. (The scheduling part of 'run_steps'.)
+fn schedule<F>(
+    managers: &[(&str, Vec<&BuildStep>)],
+    edges: &[Edge],
+    jobs: usize,
+    run: F,
+) -> Result<(), io::Error>
+where
+    F: Fn(&[&BuildStep], &Job) -> Result<(), io::Error> + Sync,
+{
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::channel::<(String, Result<(), io::Error>)>();
+
+    let mut started: Vec<&str> = Vec::new();
+    let mut done: Vec<String> = Vec::new();
+    let mut running: usize = 0;
+    let mut failure: Option<io::Error> = None;
+
+    thread::scope(|scope| loop {
+        if failure.is_none() {
+            for (man, man_steps) in managers.iter() {
+                if running >= jobs {
+                    break;
+                }
+
+                if started.contains(man) {
+                    continue;
+                }
+
+                // Managers without steps don't have a job to wait for.
+                let ready = edges
+                    .iter()
+                    .filter(|(_, b)| b == man)
+                    .all(|(a, _)| done.contains(a) || !managers.iter().any(|x| x.0 == a));
+
+                if !ready {
+                    continue;
+                }
+
+                started.push(man);
+                running += 1;
+
+                info!("Starting job: {man}");
+
+                let job = Job::new(man, cancelled.clone());
+                let sender = sender.clone();
+                let run = &run;
+
+                scope.spawn(move || {
+                    job::set_current(Some(job.clone()));
+
+                    let result = run(man_steps, &job);
+
+                    if result.is_err() {
+                        job.cancel();
+                    }
+
+                    job::set_current(None);
+
+                    let _ = sender.send((job.name, result));
+                });
+            }
+        }
+
+        if running == 0 {
+            break;
+        }
+
+        let (man, result) = receiver.recv().unwrap();
+
+        running -= 1;
+
+        match result {
+            Ok(_) => {
+                success!("Finished job: {man}");
+
+                done.push(man);
+            }
+            Err(e) => match failure {
+                Some(_) => note!("Cancelled job: {man}"),
+                None => {
+                    error!("Job failed: {man}");
+
+                    if running > 0 {
+                        warning!("Cancelling the other jobs...");
+                    }
+
+                    failure = Some(e);
+                }
+            },
+        };
+    });
+
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+// Run the steps of one manager, one after another.
+fn run_job(
+    steps: &[&BuildStep],
+    journal: &Mutex<&mut Journal>,
+    job: &Job,
+) -> Result<(), io::Error> {
+    for step in steps.iter() {
+        let step_id = step.id();
+
+        if journal.lock().unwrap().is_done(&step_id) {
+            note!("Skipping '{step_id}', it was already done.");
+
+            continue;
+        }
+
+        if job.is_cancelled() {
+            return Err(custom_error("Job was cancelled!"));
+        }
+
+        let man = load_manager(&step.manager)?;
+
+        match step.mode {
+            HistoryMode::Remove => man.remove(&step.items)?,
+            HistoryMode::Add | HistoryMode::Change => man.add(&step.items)?,
+        };
+
+        journal.lock().unwrap().mark_done(&step_id)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn step(manager: &str) -> BuildStep {
+        BuildStep {
+            manager: manager.to_string(),
+            mode: HistoryMode::Add,
+            items: Vec::new(),
+        }
+    }
+
+    fn edge(a: &str, b: &str) -> Edge {
+        (a.to_string(), b.to_string())
+    }
+
+    // Schedule one step per manager, and return what happened. ("start NAME" and "end NAME")
+    fn events(
+        names: &[&str],
+        edges: &[Edge],
+        jobs: usize,
+        run: impl Fn(&str, &Job) -> Result<(), io::Error> + Sync,
+    ) -> (Vec<String>, Result<(), io::Error>) {
+        let steps: Vec<BuildStep> = names.iter().map(|x| step(x)).collect();
+        let managers: Vec<(&str, Vec<&BuildStep>)> = steps
+            .iter()
+            .map(|x| (x.manager.as_str(), vec![x]))
+            .collect();
+
+        let events: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+        let result = schedule(&managers, edges, jobs, |man_steps, job| {
+            let man = &man_steps[0].manager;
+
+            events.lock().unwrap().push(format!("start {man}"));
+
+            let result = run(man, job);
+
+            events.lock().unwrap().push(format!("end {man}"));
+
+            result
+        });
+
+        (events.into_inner().unwrap(), result)
+    }
+
+    fn position(events: &[String], event: &str) -> usize {
+        events.iter().position(|x| x == event).unwrap()
+    }
+
+    #[test]
+    fn jobs_wait_for_the_managers_they_come_after() {
+        let (events, result) = events(&["a", "b", "c"], &[edge("a", "b")], 2, |man, _| {
+            if man == "a" {
+                thread::sleep(Duration::from_millis(100));
+            }
+
+            Ok(())
+        });
+
+        assert!(result.is_ok());
+        assert_eq!(events.len(), 6);
+        assert!(position(&events, "end a") < position(&events, "start b"));
+        // 'c' doesn't have to wait, so it runs next to 'a'.
+        assert!(position(&events, "start c") < position(&events, "end a"));
+    }
+
+    #[test]
+    fn edges_to_managers_without_steps_are_ignored() {
+        let (events, result) = events(&["a"], &[edge("nothing", "a")], 1, |_, _| Ok(()));
+
+        assert!(result.is_ok());
+        assert_eq!(events, vec!["start a", "end a"]);
+    }
+
+    #[test]
+    fn no_more_jobs_than_allowed_run_at_once() {
+        let running = Mutex::new((0, 0)); // (Running now, most at once.)
+
+        let (_, result) = events(&["a", "b", "c", "d", "e"], &[], 2, |_, _| {
+            {
+                let mut running = running.lock().unwrap();
+                running.0 += 1;
+                running.1 = running.1.max(running.0);
+            }
+
+            thread::sleep(Duration::from_millis(50));
+
+            running.lock().unwrap().0 -= 1;
+
+            Ok(())
+        });
+
+        assert!(result.is_ok());
+        assert_eq!(running.into_inner().unwrap(), (0, 2));
+    }
+
+    #[test]
+    fn a_failure_cancels_the_other_jobs() {
+        let (events, result) = events(&["a", "b", "c"], &[edge("a", "b")], 2, |man, job| {
+            if man == "a" {
+                return Err(custom_error("Failed!"));
+            }
+
+            // 'c' runs until it gets cancelled.
+            while !job.is_cancelled() {
+                thread::sleep(Duration::from_millis(10));
+            }
+
+            Err(custom_error("Job was cancelled!"))
+        });
+
+        assert_eq!(result.unwrap_err().to_string(), "Failed!");
+        // 'b' comes after the failed job, so it never starts.
+        assert_eq!(events.len(), 4);
+        assert!(!events.contains(&String::from("start b")));
+    }
+}
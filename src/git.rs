@@ -0,0 +1,142 @@
+#![allow(dead_code)]
+
+use fspp::*;
+use piglog::prelude::*;
+use piglog::*;
+use std::io;
+use std::process::Command;
+
+use crate::library::*;
+
+// Run git in a directory, and get its output. (None if git failed, or isn't installed.)
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir.to_string())
+        .args(args)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8(output.stdout).ok()
+}
+
+// Run git in a directory, letting it print to the terminal. (Returns whether it succeeded.)
+fn git_visible(dir: &Path, args: &[&str]) -> bool {
+    Command::new("git")
+        .arg("-C")
+        .arg(dir.to_string())
+        .args(args)
+        .status()
+        .is_ok_and(|x| x.success())
+}
+
+// Is the directory inside of a git repository?
+pub fn is_repo(dir: &Path) -> bool {
+    git(dir, &["rev-parse", "--is-inside-work-tree"]).is_some_and(|x| x.trim() == "true")
+}
+
+// Is the directory the top of its own git repository? (Not just somewhere inside of a bigger one.)
+pub fn is_repo_root(dir: &Path) -> bool {
+    let toplevel = match git(dir, &["rev-parse", "--show-toplevel"]) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    match (
+        std::fs::canonicalize(toplevel.trim()),
+        std::fs::canonicalize(dir.to_string()),
+    ) {
+        (Ok(toplevel), Ok(dir)) => toplevel == dir,
+        _ => false,
+    }
+}
+
+// The commit that HEAD points to. (None if there are no commits yet.)
+pub fn head(dir: &Path) -> Option<String> {
+    git(dir, &["rev-parse", "HEAD"]).map(|x| x.trim().to_string())
+}
+
+// Does the directory have uncommitted changes? (Untracked files count.)
+pub fn is_dirty(dir: &Path) -> bool {
+    git(dir, &["status", "--porcelain", "--", "."]).is_some_and(|x| !x.trim().is_empty())
+}
+
+// Is the file untracked? (Ignored files don't count.)
+pub fn is_untracked(dir: &Path, file: &Path) -> bool {
+    git(dir, &["status", "--porcelain", "--", &file.to_string()])
+        .is_some_and(|x| x.starts_with("??"))
+}
+
+// Commit everything in the directory. (Nothing outside of it, even if the repository is bigger.)
+pub fn commit_all(dir: &Path, msg: &str) -> Result<(), io::Error> {
+    if git(dir, &["add", "--all", "--", "."]).is_none() {
+        error!("Failed to stage changes in git! ({})", dir.to_string());
+        return Err(custom_error("Failed to stage changes in git!"));
+    }
+
+    if git(dir, &["commit", "--quiet", "--message", msg, "--", "."]).is_none() {
+        error!("Failed to create git commit! ({})", dir.to_string());
+        return Err(custom_error("Failed to create git commit!"));
+    }
+
+    Ok(())
+}
+
+// Clone a repository into a directory. ('parent' is where git runs, it has to exist.)
+pub fn clone(source: &str, parent: &Path, dir: &Path) -> Result<(), io::Error> {
+    if !git_visible(parent, &["clone", "--quiet", source, &dir.to_string()]) {
+        error!("Failed to clone git repository! ({source})");
+        return Err(custom_error("Failed to clone git repository!"));
+    }
+
+    Ok(())
+}
+
+// Pull from a repository. (Only fast-forwards, local history is never rewritten or merged.)
+pub fn pull(dir: &Path, source: &str) -> Result<(), io::Error> {
+    if !git_visible(dir, &["pull", "--quiet", "--ff-only", source]) {
+        error!("Failed to pull from git repository! ({source})");
+        return Err(custom_error("Failed to pull from git repository!"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn commit_all_leaves_the_rest_of_the_repository_alone() {
+        let root = test_util::temp_dir("git-commit-all");
+        let _env = test_util::sandbox(&root);
+
+        test_util::repo(&root, "Initial");
+        test_util::write(&root.join("elsewhere.txt"), "staged by the user\n");
+        test_util::git(&root, &["add", "elsewhere.txt"]);
+        test_util::write(&root.join("rebos/gen.toml"), "imports = []\n");
+
+        let dir = Path::new(&root.join("rebos").to_string_lossy());
+
+        commit_all(&dir, "Config").unwrap();
+
+        let committed = test_util::git(&root, &["show", "--name-only", "--format=%s", "HEAD"]);
+
+        assert_eq!(
+            committed.split_whitespace().collect::<Vec<_>>(),
+            vec!["Config", "rebos/gen.toml"]
+        );
+        assert_eq!(
+            test_util::git(&root, &["diff", "--cached", "--name-only"]).trim(),
+            "elsewhere.txt"
+        );
+        assert!(!is_dirty(&dir));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
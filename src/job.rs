@@ -0,0 +1,102 @@
+#![allow(dead_code)]
+
+use colored::Colorize;
+use std::cell::RefCell;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::library::{wait_for_child, CommandOutcome};
+use crate::proc::spawn_in_group;
+
+/// A unit of work running next to others. (Commands run by its thread get their output
+/// prefixed with the job name, and are killed when the job gets cancelled.)
+#[derive(Clone)]
+pub struct Job {
+    pub name: String,
+    /// Shared by all jobs of a build. Set when any of them fails.
+    pub cancelled: Arc<AtomicBool>,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Job>> = const { RefCell::new(None) };
+}
+
+// Make commands run by this thread belong to a job. (None to run them normally again.)
+pub fn set_current(job: Option<Job>) {
+    CURRENT.with(|x| *x.borrow_mut() = job);
+}
+
+// The job of this thread, if it has one.
+pub fn current() -> Option<Job> {
+    CURRENT.with(|x| x.borrow().clone())
+}
+
+impl Job {
+    pub fn new(name: &str, cancelled: Arc<AtomicBool>) -> Self {
+        Self {
+            name: name.to_string(),
+            cancelled,
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    fn prefix(&self) -> String {
+        format!("[{}]", self.name).bright_cyan().bold().to_string()
+    }
+
+    // Run a command with its output prefixed. (Commands can't read input, since other jobs run at the same time.)
+    pub fn run(&self, mut command: Command, timeout: Option<Duration>) -> CommandOutcome {
+        if self.is_cancelled() {
+            return CommandOutcome::Failure;
+        }
+
+        // The command gets its own process group, so cancelling also kills whatever it started.
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let (mut child, _terminal) = match spawn_in_group(&mut command, false) {
+            Ok(o) => o,
+            Err(_e) => return CommandOutcome::Failure,
+        };
+
+        let readers = [
+            self.forward(child.stdout.take().unwrap(), false),
+            self.forward(child.stderr.take().unwrap(), true),
+        ];
+
+        let outcome = wait_for_child(&mut child, timeout, || self.is_cancelled());
+
+        for r in readers {
+            let _ = r.join();
+        }
+
+        outcome
+    }
+
+    // Print every line of a stream with the job prefix.
+    fn forward<R: Read + Send + 'static>(&self, stream: R, stderr: bool) -> thread::JoinHandle<()> {
+        let prefix = self.prefix();
+
+        thread::spawn(move || {
+            for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                match stderr {
+                    true => eprintln!("{prefix} {line}"),
+                    false => println!("{prefix} {line}"),
+                };
+            }
+        })
+    }
+}
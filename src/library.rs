@@ -4,21 +4,49 @@ use colored::Colorize;
 use hashbrown::HashMap;
 use piglog::prelude::*;
 use std::io;
-use std::process::Command;
+use std::process::{Child, Command};
+use std::thread;
+use std::time::{Duration, Instant};
 use users::get_current_username;
 
 use crate::convert::*;
-use crate::generation::Generation;
+use crate::generation::{Generation, Item};
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy, Debug)]
 pub enum HistoryMode {
     Remove,
     Add,
+    /// The item stayed, but its version changed.
+    Change,
 }
 
 pub struct History {
     pub mode: HistoryMode,
-    pub line: String,
+    pub item: Item,
+    /// What the item was before. (Only for HistoryMode::Change.)
+    pub old: Option<Item>,
+}
+
+impl History {
+    pub fn new(mode: HistoryMode, item: &Item) -> Self {
+        Self {
+            mode,
+            item: item.clone(),
+            old: None,
+        }
+    }
+
+    pub fn line(&self) -> String {
+        match self.old {
+            Some(ref old) => format!(
+                "{} ({} -> {})",
+                self.item.name,
+                old.version.as_deref().unwrap_or("any"),
+                self.item.version.as_deref().unwrap_or("any"),
+            ),
+            None => self.item.to_string(),
+        }
+    }
 }
 
 pub fn abort() {
@@ -27,11 +55,131 @@ pub fn abort() {
 }
 
 pub fn run_command(command: &str) -> bool {
-    match Command::new("bash").args(["-c", command]).status() {
+    run_command_timeout(command, None) == CommandOutcome::Success
+}
+
+/// How running a command went.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum CommandOutcome {
+    Success,
+    Failure,
+    /// The command took too long, and was killed.
+    TimedOut,
+}
+
+/// A command to run, either through bash, or directly as a program with arguments.
+#[derive(PartialEq, Clone, Debug)]
+pub enum CommandLine {
+    Shell(String),
+    /// The program, then its arguments. (No shell is involved, so nothing gets interpreted.)
+    Argv(Vec<String>),
+}
+
+impl CommandLine {
+    pub fn to_command(&self) -> Command {
+        match self {
+            Self::Shell(s) => {
+                let mut command = Command::new("bash");
+                command.args(["-c", s]);
+                command
+            }
+            Self::Argv(a) => {
+                let mut command = Command::new(a.first().map(|x| x.as_str()).unwrap_or_default());
+                command.args(a.iter().skip(1));
+                command
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for CommandLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Shell(s) => write!(f, "{s}"),
+            Self::Argv(a) => write!(
+                f,
+                "{}",
+                a.iter()
+                    .map(|x| shell_quote(x))
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            ),
+        }
+    }
+}
+
+// Quote a string for bash, so it is always a single word, and nothing in it gets interpreted.
+pub fn shell_quote(s: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c);
+
+    if !s.is_empty() && s.chars().all(safe) {
+        return s.to_string();
+    }
+
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+// Run a command, and kill it if it takes longer than the timeout.
+pub fn run_command_timeout(command: &str, timeout: Option<Duration>) -> CommandOutcome {
+    run_command_line(&CommandLine::Shell(command.to_string()), timeout)
+}
+
+// Run a command line, and kill it if it takes longer than the timeout.
+pub fn run_command_line(line: &CommandLine, timeout: Option<Duration>) -> CommandOutcome {
+    // Commands of parallel build jobs get their output prefixed, and can be cancelled.
+    if let Some(job) = crate::job::current() {
+        return job.run(line.to_command(), timeout);
+    }
+
+    let (mut child, terminal) = match crate::proc::spawn_in_group(&mut line.to_command(), true) {
         Ok(o) => o,
-        Err(_e) => return false,
+        Err(_e) => return CommandOutcome::Failure,
+    };
+
+    let leader = child.id();
+
+    let outcome = wait_for_child(&mut child, timeout, || {
+        terminal.forward_stop(leader);
+        false
+    });
+
+    terminal.forward_interrupt(&mut child);
+
+    outcome
+}
+
+// Wait for a command started with 'proc::spawn_in_group' to exit. It gets killed (along with
+// what it started) if it takes longer than the timeout, or if 'cancelled' returns true.
+pub fn wait_for_child(
+    child: &mut Child,
+    timeout: Option<Duration>,
+    cancelled: impl Fn() -> bool,
+) -> CommandOutcome {
+    let start = Instant::now();
+
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => match status.success() {
+                true => return CommandOutcome::Success,
+                false => return CommandOutcome::Failure,
+            },
+            Ok(None) => (),
+            Err(_e) => return CommandOutcome::Failure,
+        };
+
+        let timed_out = timeout.is_some_and(|x| start.elapsed() >= x);
+
+        if timed_out || cancelled() {
+            crate::proc::kill_group(child);
+
+            return match timed_out {
+                true => CommandOutcome::TimedOut,
+                false => CommandOutcome::Failure,
+            };
+        }
+
+        thread::sleep(Duration::from_millis(50));
     }
-    .success()
 }
 
 pub fn run_command_with_output(command: &str) -> Option<String> {
@@ -105,6 +253,41 @@ pub fn username() -> String {
     username
 }
 
+// The current time. (Unix timestamp, in seconds.)
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or(0)
+}
+
+// Format a unix timestamp as a UTC date and time. (Example: 2024-05-01 13:37 UTC)
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds_of_day = timestamp % 86400;
+
+    // Days since 1970-01-01 -> civil date. (Howard Hinnant's algorithm.)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        (seconds_of_day % 3600) / 60
+    )
+}
+
 pub fn remove_array_duplicates<T: Clone + PartialEq>(dup_vec: &[T]) -> Vec<T> {
     let mut new_vec: Vec<T> = Vec::new();
 
@@ -132,10 +315,7 @@ pub fn history_gen(gen_1: &Generation, gen_2: &Generation) -> HashMap<String, Ve
                 items_2
                     .items
                     .iter()
-                    .map(|x| History {
-                        mode: HistoryMode::Add,
-                        line: x.to_string(),
-                    })
+                    .map(|x| History::new(HistoryMode::Add, x))
                     .collect(),
             ),
         };
@@ -152,10 +332,7 @@ pub fn history_gen(gen_1: &Generation, gen_2: &Generation) -> HashMap<String, Ve
                     items_1
                         .items
                         .iter()
-                        .map(|x| History {
-                            mode: HistoryMode::Remove,
-                            line: x.to_string(),
-                        })
+                        .map(|x| History::new(HistoryMode::Remove, x))
                         .collect(),
                 );
             }
@@ -178,39 +355,62 @@ pub fn print_history_gen(history: &HashMap<String, Vec<History>>) {
 pub fn print_history(diff_vec: &Vec<History>) {
     for i in diff_vec.iter() {
         match i.mode {
-            HistoryMode::Add => println!("{}", format!("+ {}", i.line).bright_green().bold()),
-            HistoryMode::Remove => println!("{}", format!("- {}", i.line).bright_red().bold()),
+            HistoryMode::Add => println!("{}", format!("+ {}", i.line()).bright_green().bold()),
+            HistoryMode::Remove => println!("{}", format!("- {}", i.line()).bright_red().bold()),
+            HistoryMode::Change => {
+                println!("{}", format!("~ {}", i.line()).bright_yellow().bold())
+            }
         };
     }
 }
 
-pub fn history(array_1: &[String], array_2: &[String]) -> Vec<History> {
+// Items are matched by name, so a new version of an item is a change, not a removal and an addition.
+pub fn history(array_1: &[Item], array_2: &[Item]) -> Vec<History> {
     let lines_1 = remove_array_duplicates(array_1);
     let lines_2 = remove_array_duplicates(array_2);
 
     let mut history_vec: Vec<History> = Vec::new();
 
     for i in lines_1.iter() {
-        if i.trim() != "" {
-            if lines_2.contains(i) == false {
-                history_vec.push(History {
-                    mode: HistoryMode::Remove,
-                    line: i.to_string(),
-                });
-            }
+        if i.name.trim() != "" && !lines_2.iter().any(|x| x.name == i.name) {
+            history_vec.push(History::new(HistoryMode::Remove, i));
         }
     }
 
     for i in lines_2.iter() {
-        if i.trim() != "" {
-            if lines_1.contains(i) == false {
-                history_vec.push(History {
-                    mode: HistoryMode::Add,
-                    line: i.to_string(),
-                });
-            }
+        if i.name.trim() != "" {
+            match lines_1.iter().find(|x| x.name == i.name) {
+                None => history_vec.push(History::new(HistoryMode::Add, i)),
+                Some(old) => {
+                    if old.version != i.version {
+                        history_vec.push(History {
+                            mode: HistoryMode::Change,
+                            item: i.clone(),
+                            old: Some(old.clone()),
+                        });
+                    }
+                }
+            };
         }
     }
 
     history_vec
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_timestamp(1709210096), "2024-02-29 12:34 UTC");
+        assert_eq!(format_timestamp(951782400), "2000-02-29 00:00 UTC");
+        // Year boundary.
+        assert_eq!(format_timestamp(946684799), "1999-12-31 23:59 UTC");
+        assert_eq!(format_timestamp(946684800), "2000-01-01 00:00 UTC");
+        // 2100 isn't a leap year.
+        assert_eq!(format_timestamp(4107542340), "2100-02-28 23:59 UTC");
+        assert_eq!(format_timestamp(4107542400), "2100-03-01 00:00 UTC");
+    }
+}
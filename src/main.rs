@@ -3,21 +3,31 @@ mod cli; // For argument parsing and command structuring.
 mod config; // Configuration stuff.
 mod convert; // Convert one type to another type.
 mod generation; // The generations system.
+mod git; // Git integration for the config directory.
 mod hook; // Hook stuff.
+mod job; // Running commands of parallel build jobs.
 mod library; // Full of functions.
 mod lock; // Locking file functionality.
 mod management; // Stuff related to item management.
 mod obj_print; // Print objects.
 mod obj_print_boilerplate; // Boilerplate code for obj print.
+mod output; // Machine-readable output.
+mod placeholder; // Placeholders in manager commands.
 mod places; // Where is stuff stored?
+mod presets; // Bundled manager configurations.
 mod proc;
+mod settings; // Rebos settings.
 mod system; // Used for getting system information. // Process management stuff for Rebos.
+#[cfg(test)]
+mod test_util; // Helpers for tests.
 
 // Import stuff from source files and crates.
 use clap::Parser;
+use cli::OutputFormat;
 use colored::Colorize;
 use config::ConfigSide;
 use fspp::*;
+use generation::CommitOutcome;
 use library::*;
 use piglog::prelude::*;
 use piglog::*;
@@ -28,6 +38,16 @@ use std::io::{self, Write};
 enum ExitCode {
     Success,
     Fail,
+    /// The system doesn't match the built generation.
+    Drift,
+    /// There was nothing to commit.
+    Unchanged,
+    /// The user config has uncommitted changes.
+    Uncommitted,
+    /// The 'current' generation isn't built.
+    NotBuilt,
+    /// Both of the above.
+    UncommittedNotBuilt,
 }
 
 // Use this function for testing code!
@@ -54,6 +74,31 @@ fn main() -> std::process::ExitCode {
 
             std::process::ExitCode::FAILURE
         }
+        ExitCode::Drift => {
+            error_cleanup();
+
+            std::process::ExitCode::from(2)
+        }
+        ExitCode::Unchanged => {
+            error_cleanup();
+
+            std::process::ExitCode::from(3)
+        }
+        ExitCode::Uncommitted => {
+            error_cleanup();
+
+            std::process::ExitCode::from(4)
+        }
+        ExitCode::NotBuilt => {
+            error_cleanup();
+
+            std::process::ExitCode::from(5)
+        }
+        ExitCode::UncommittedNotBuilt => {
+            error_cleanup();
+
+            std::process::ExitCode::from(6)
+        }
     }
 }
 
@@ -136,15 +181,38 @@ fn app() -> ExitCode {
 
             match command {
                 cli::GenCommands::Commit(c) => {
-                    info!("Committing user generation...");
+                    if !c.if_changed {
+                        info!("Committing user generation...");
+                    }
+
+                    let options = generation::CommitOptions {
+                        allow_empty: c.allow_empty,
+                        amend: c.amend,
+                    };
 
-                    match generation::commit(c.msg.as_str()) {
-                        Ok(_) => success!("Committed generation successfully! (\"{}\")", c.msg),
+                    match generation::commit(c.msg.as_deref(), &options) {
+                        Ok(CommitOutcome::Committed(n)) => {
+                            success!("Committed generation successfully! (Generation {n})")
+                        }
+                        Ok(CommitOutcome::Amended(n)) => {
+                            success!("Amended generation successfully! (Generation {n})")
+                        }
+                        Ok(CommitOutcome::Unchanged(_)) if c.if_changed => (),
+                        Ok(CommitOutcome::Unchanged(n)) => {
+                            error!("Nothing changed since generation {n}! (Use '--allow-empty' to commit anyway.)");
+                            return ExitCode::Unchanged;
+                        }
                         Err(_) => return ExitCode::Fail,
                     };
                 }
                 cli::GenCommands::List => {
-                    match generation::list_print() {
+                    let result = match args.output {
+                        OutputFormat::Text => generation::list_print(),
+                        OutputFormat::Json => generation::list()
+                            .and_then(|o| output::print_json(&generation::sort_list_vector(&o))),
+                    };
+
+                    match result {
                         Ok(_) => (),
                         Err(_) => return ExitCode::Fail,
                     };
@@ -167,22 +235,111 @@ fn app() -> ExitCode {
                         Err(_) => return ExitCode::Fail,
                     };
                 }
-                cli::GenCommands::Info => {
-                    let generation = match generation::gen(ConfigSide::User) {
+                cli::GenCommands::Info(i) => {
+                    let (generation, provenance) =
+                        match generation::gen_with_provenance(ConfigSide::User) {
+                            Ok(o) => o,
+                            Err(_) => return ExitCode::Fail,
+                        };
+
+                    if let Some(ref item) = i.explain {
+                        let declarations: Vec<generation::imports::Declaration> = provenance
+                            .into_iter()
+                            .filter(|x| x.item.name == *item)
+                            .collect();
+
+                        match args.output {
+                            OutputFormat::Text => {
+                                if declarations.is_empty() {
+                                    error!("Item '{item}' is not declared anywhere!");
+                                    return ExitCode::Fail;
+                                }
+
+                                obj_print::declarations(&declarations);
+
+                                if declarations.iter().all(|x| x.excluded_by.is_some()) {
+                                    error!("Item '{item}' is declared, but excluded!");
+                                    return ExitCode::Fail;
+                                }
+                            }
+                            OutputFormat::Json => {
+                                match output::print_json(&declarations) {
+                                    Ok(_) => (),
+                                    Err(_) => return ExitCode::Fail,
+                                };
+
+                                // Not declared anywhere, or excluded everywhere.
+                                if declarations.iter().all(|x| x.excluded_by.is_some()) {
+                                    return ExitCode::Fail;
+                                }
+                            }
+                        };
+                    } else {
+                        match args.output {
+                            OutputFormat::Text => obj_print::generation(&generation),
+                            OutputFormat::Json => {
+                                match output::print_json(&output::info(&generation)) {
+                                    Ok(_) => (),
+                                    Err(_) => return ExitCode::Fail,
+                                };
+                            }
+                        };
+                    }
+                }
+                cli::GenCommands::Latest => {
+                    let latest = match generation::latest_number() {
                         Ok(o) => o,
                         Err(_) => return ExitCode::Fail,
                     };
 
-                    obj_print::generation(&generation);
+                    match args.output {
+                        OutputFormat::Text => info!("Latest generation number is: {}", latest),
+                        OutputFormat::Json => {
+                            match output::print_json(&output::Latest { latest }) {
+                                Ok(_) => (),
+                                Err(_) => return ExitCode::Fail,
+                            };
+                        }
+                    };
                 }
-                cli::GenCommands::Latest => {
-                    info!(
-                        "Latest generation number is: {}",
-                        match generation::latest_number() {
-                            Ok(o) => o,
-                            Err(_) => return ExitCode::Fail,
+                cli::GenCommands::Show(g) => {
+                    if !generation::gen_exists(g.generation) {
+                        fatal!("Generation not found!");
+
+                        return ExitCode::Fail;
+                    }
+
+                    let (gen, commit, meta) = match (
+                        generation::get_gen_from_usize(g.generation),
+                        generation::get_gen_commit_from_usize(g.generation),
+                        generation::metadata::read(g.generation),
+                    ) {
+                        (Ok(gen), Ok(commit), Ok(meta)) => (gen, commit, meta),
+                        _ => return ExitCode::Fail,
+                    };
+
+                    match args.output {
+                        OutputFormat::Text => {
+                            obj_print::generation_header(g.generation, &commit, &meta);
+                            obj_print::generation(&gen);
                         }
-                    );
+                        OutputFormat::Json => {
+                            let info = output::info(&gen);
+
+                            let show = output::Show {
+                                number: g.generation,
+                                commit,
+                                metadata: meta,
+                                managers: info.managers,
+                                versions: info.versions,
+                            };
+
+                            match output::print_json(&show) {
+                                Ok(_) => (),
+                                Err(_) => return ExitCode::Fail,
+                            };
+                        }
+                    };
                 }
                 cli::GenCommands::DeleteOld(h) => {
                     info!("Deleting old generations...");
@@ -198,41 +355,156 @@ fn app() -> ExitCode {
                         Err(_) => return ExitCode::Fail,
                     };
                 }
-                cli::GenCommands::Diff { old, new } => {
-                    if generation::gen_exists(*old) == false
-                        || generation::gen_exists(*new) == false
-                    {
+                cli::GenCommands::Gc(g) => {
+                    info!("Collecting old generations...");
+
+                    match generation::management::gc(g.dry_run, g.yes) {
+                        Ok(o) => {
+                            if !g.dry_run {
+                                success!("Deleted {o} generations!");
+                            }
+                        }
+                        Err(_) => return ExitCode::Fail,
+                    };
+                }
+                cli::GenCommands::Export(e) => {
+                    if !generation::gen_exists(e.generation) {
                         fatal!("Generation not found!");
 
                         return ExitCode::Fail;
                     }
 
-                    let gen_1 = generation::get_gen_from_usize(*old).unwrap();
-                    let gen_2 = generation::get_gen_from_usize(*new).unwrap();
+                    let bundle_string = match generation::bundle::export(e.generation)
+                        .and_then(|o| generation::bundle::to_string(&o))
+                    {
+                        Ok(o) => o,
+                        Err(_) => return ExitCode::Fail,
+                    };
 
-                    let commit_1 = generation::get_gen_commit_from_usize(*old).unwrap();
-                    let commit_2 = generation::get_gen_commit_from_usize(*new).unwrap();
+                    match e.file {
+                        Some(ref f) => match file::write(&bundle_string, &Path::new(f)) {
+                            Ok(_) => success!("Exported generation {} to: {f}", e.generation),
+                            Err(_) => {
+                                fatal!("Failed to write bundle! ({f})");
 
-                    let history = library::history_gen(&gen_1, &gen_2);
+                                return ExitCode::Fail;
+                            }
+                        },
+                        None => print!("{bundle_string}"),
+                    };
+                }
+                cli::GenCommands::Import(i) => {
+                    info!("Importing bundle...");
 
-                    println!(
-                        "\n{} {} {}",
-                        commit_1.bright_cyan().bold(),
-                        "->".bright_black().bold(),
-                        commit_2.bright_cyan().bold()
-                    );
+                    let result = generation::bundle::read(&Path::new(&i.file))
+                        .and_then(|o| generation::bundle::import(&o, i.replace_managers));
+
+                    match result {
+                        Ok(o) => success!("Imported bundle as generation {o}!"),
+                        Err(_) => return ExitCode::Fail,
+                    };
+                }
+                cli::GenCommands::Pin(p) => {
+                    match generation::pin(p.generation, true) {
+                        Ok(_) => (),
+                        Err(_) => return ExitCode::Fail,
+                    };
+                }
+                cli::GenCommands::Unpin(p) => {
+                    match generation::unpin(p.generation, true) {
+                        Ok(_) => (),
+                        Err(_) => return ExitCode::Fail,
+                    };
+                }
+                cli::GenCommands::Diff { old, new, .. } => {
+                    let old = match old {
+                        Some(s) => *s,
+                        None => match generation::get_current() {
+                            Ok(o) => o,
+                            Err(_) => return ExitCode::Fail,
+                        },
+                    };
+
+                    if !generation::gen_exists(old)
+                        || new.is_some_and(|x| !generation::gen_exists(x))
+                    {
+                        fatal!("Generation not found!");
+
+                        return ExitCode::Fail;
+                    }
 
-                    println!("");
+                    let gen_1 = generation::get_gen_from_usize(old).unwrap();
+                    let commit_1 = generation::get_gen_commit_from_usize(old).unwrap();
+
+                    // Without a new generation, '--user' was given.
+                    let (gen_2, commit_2) = match new {
+                        Some(n) => (
+                            generation::get_gen_from_usize(*n).unwrap(),
+                            generation::get_gen_commit_from_usize(*n).unwrap(),
+                        ),
+                        None => match generation::gen(ConfigSide::User) {
+                            Ok(o) => (o, String::from("User config (uncommitted)")),
+                            Err(_) => return ExitCode::Fail,
+                        },
+                    };
+
+                    let history = library::history_gen(&gen_1, &gen_2);
 
-                    library::print_history_gen(&history);
+                    if args.output == OutputFormat::Json {
+                        let diff = output::Diff {
+                            old: output::DiffSide {
+                                number: Some(old),
+                                commit: commit_1,
+                            },
+                            new: output::DiffSide {
+                                number: *new,
+                                commit: commit_2,
+                            },
+                            managers: output::changes(&history),
+                        };
+
+                        match output::print_json(&diff) {
+                            Ok(_) => (),
+                            Err(_) => return ExitCode::Fail,
+                        };
+                    } else {
+                        println!(
+                            "\n{} {} {}",
+                            generation::message::first_line(&commit_1)
+                                .bright_cyan()
+                                .bold(),
+                            "->".bright_black().bold(),
+                            generation::message::first_line(&commit_2)
+                                .bright_cyan()
+                                .bold()
+                        );
+
+                        println!();
+
+                        library::print_history_gen(&history);
+                    }
                 }
                 cli::GenCommands::Current { command } => {
                     match command {
-                        cli::CurrentCommands::Build => {
-                            info!("Building 'current' generation...");
+                        cli::CurrentCommands::Build(b) => {
+                            let options = generation::BuildOptions {
+                                resume: b.resume,
+                                dry_run: b.dry_run,
+                                jobs: b.jobs,
+                            };
 
-                            match generation::build() {
-                                Ok(_) => success!("Built generation successfully!"),
+                            if options.dry_run {
+                                info!("Planning build of 'current' generation...");
+                            } else {
+                                info!("Building 'current' generation...");
+                            }
+
+                            match generation::build(&options) {
+                                Ok(_) => {
+                                    if !options.dry_run {
+                                        success!("Built generation successfully!");
+                                    }
+                                }
                                 Err(_) => return ExitCode::Fail,
                             };
                         }
@@ -260,6 +532,67 @@ fn app() -> ExitCode {
                                 Err(_) => return ExitCode::Fail,
                             };
                         }
+                        cli::CurrentCommands::Verify => {
+                            let built_gen = match generation::built_gen() {
+                                Ok(o) => o,
+                                Err(_) => return ExitCode::Fail,
+                            };
+
+                            let (missing_count, complete) = match args.output {
+                                OutputFormat::Text => {
+                                    info!("Verifying built generation...");
+
+                                    match management::verify(&built_gen) {
+                                        Ok(o) => o,
+                                        Err(_) => return ExitCode::Fail,
+                                    }
+                                }
+                                OutputFormat::Json => {
+                                    let missing = match management::get_missing(&built_gen) {
+                                        Ok(o) => o.missing,
+                                        Err(_) => return ExitCode::Fail,
+                                    };
+
+                                    let missing_count = missing.iter().map(|x| x.1.len()).sum();
+
+                                    match output::print_json(
+                                        &missing.into_iter().collect::<output::Missing>(),
+                                    ) {
+                                        Ok(_) => (),
+                                        Err(_) => return ExitCode::Fail,
+                                    };
+
+                                    (missing_count, true)
+                                }
+                            };
+
+                            if missing_count > 0 {
+                                if args.output == OutputFormat::Text {
+                                    error!("{missing_count} items of the built generation are not installed!");
+                                }
+
+                                return ExitCode::Drift;
+                            }
+
+                            if args.output == OutputFormat::Text {
+                                match complete {
+                                    true => {
+                                        success!("All items of the built generation are installed!")
+                                    }
+                                    false => {
+                                        success!("All items that could be checked are installed!")
+                                    }
+                                };
+                            }
+                        }
+                        cli::CurrentCommands::Repair => {
+                            info!("Repairing built generation...");
+
+                            match generation::repair() {
+                                Ok(_) => success!("Repaired generation successfully!"),
+                                Err(_) => return ExitCode::Fail,
+                            };
+                        }
                         _ => {
                             error!("Command not usable yet!");
                             return ExitCode::Fail;
@@ -295,6 +628,58 @@ fn app() -> ExitCode {
                         Err(_) => return ExitCode::Fail,
                     };
                 }
+                cli::ConfigCommands::Pull(p) => {
+                    let history = match config::pull(&p.source) {
+                        Ok(o) => o,
+                        Err(_) => return ExitCode::Fail,
+                    };
+
+                    if history.is_empty() {
+                        info!("The resolved generation didn't change.");
+                    } else {
+                        info!("Changes to the resolved generation:");
+
+                        println!();
+
+                        let mut managers: Vec<&String> = history.keys().collect();
+                        managers.sort();
+
+                        for m in managers {
+                            info!("{m}:");
+
+                            print_history(&history[m]);
+
+                            println!();
+                        }
+                    }
+
+                    success!("Pulled configuration successfully!");
+
+                    if p.commit {
+                        let msg = match p.message {
+                            Some(ref s) => s.to_string(),
+                            None => format!("Pull from {}", p.source),
+                        };
+
+                        match generation::commit(Some(&msg), &generation::CommitOptions::default())
+                        {
+                            Ok(CommitOutcome::Unchanged(n)) => {
+                                info!("Nothing to commit, generation {n} is up to date.")
+                            }
+                            Ok(_) => success!("Committed generation successfully!"),
+                            Err(_) => return ExitCode::Fail,
+                        };
+                    }
+
+                    if p.build {
+                        info!("Building 'current' generation...");
+
+                        match generation::build(&generation::BuildOptions::default()) {
+                            Ok(_) => success!("Built generation successfully!"),
+                            Err(_) => return ExitCode::Fail,
+                        };
+                    }
+                }
                 cli::ConfigCommands::Check => {
                     let result = match config::check_config() {
                         Ok(o) => o,
@@ -360,6 +745,29 @@ fn app() -> ExitCode {
                 piglog::info!("Not locked... skipping...");
             }
         }
+        cli::Commands::Status => {
+            let status = match generation::status() {
+                Ok(o) => o,
+                Err(_) => return ExitCode::Fail,
+            };
+
+            let result = match args.output {
+                OutputFormat::Text => generation::status_print(&status),
+                OutputFormat::Json => output::print_json(&output::status(&status)),
+            };
+
+            match result {
+                Ok(_) => (),
+                Err(_) => return ExitCode::Fail,
+            };
+
+            match (status.has_uncommitted(), status.current_is_built()) {
+                (false, true) => (),
+                (true, true) => return ExitCode::Uncommitted,
+                (false, false) => return ExitCode::NotBuilt,
+                (true, false) => return ExitCode::UncommittedNotBuilt,
+            };
+        }
         cli::Commands::IsUnlocked => {
             match lock::is_lock_on() {
                 false => return ExitCode::Success,
@@ -368,6 +776,16 @@ fn app() -> ExitCode {
         }
         cli::Commands::Managers { command, managers } => {
             match command {
+                cli::ManagerCommands::Add {
+                    preset,
+                    name,
+                    force,
+                } => {
+                    match presets::add(preset, name.as_deref(), *force) {
+                        Ok(_) => (),
+                        Err(_) => return ExitCode::Fail,
+                    };
+                }
                 cli::ManagerCommands::Sync => {
                     match management::sync_managers(managers) {
                         Ok(_) => (),
@@ -381,7 +799,21 @@ fn app() -> ExitCode {
                     };
                 }
                 cli::ManagerCommands::ListOthers { remove } => {
-                    match management::list_others(managers, *remove) {
+                    let result = match args.output {
+                        OutputFormat::Text => management::list_others(managers, *remove),
+                        OutputFormat::Json => {
+                            if *remove {
+                                error!("'--remove' can't be used with '--output json'!");
+                                return ExitCode::Fail;
+                            }
+
+                            management::get_others(managers).and_then(|o| {
+                                output::print_json(&o.into_iter().collect::<output::Others>())
+                            })
+                        }
+                    };
+
+                    match result {
                         Ok(_) => (),
                         Err(_) => return ExitCode::Fail,
                     };
@@ -6,18 +6,35 @@ use piglog::prelude::*;
 use piglog::*;
 use serde::Deserialize;
 use std::io;
+use std::thread;
+use std::time::Duration;
 
 use crate::config::ConfigSide;
-use crate::generation::{gen, Items};
+use crate::generation::{gen, get_current_no_output, items_from_names, Generation, Item};
 use crate::library::{self, *};
 use crate::obj_print_boilerplate::macros::print_entry;
+use crate::placeholder::{self, Placeholder};
+use crate::system;
 use crate::{bool_question, places};
 
+/// Things that only work in a command run through a shell.
+const SHELL_SYNTAX: [&str; 7] = ["|", ";", "&&", ">", "<", "$(", "`"];
+
 #[derive(Deserialize, Debug)]
 #[serde(deny_unknown_fields, default)]
 pub struct ManagerConfig {
     pub many_args: bool,
     pub arg_sep: String,
+    /// With 'many_args', split the items into calls of at most this many. (None means all at once.)
+    pub max_args_per_call: Option<usize>,
+    /// Run commands through bash. (If false, commands are split on whitespace and run directly, so items are never interpreted.)
+    pub shell: bool,
+    /// Seconds after which add, remove, sync and upgrade commands get killed.
+    pub timeout: Option<u64>,
+    /// How many more times to try a command that failed or timed out.
+    pub retries: u32,
+    /// Seconds to wait before trying again.
+    pub retry_delay: u64,
 }
 
 impl Default for ManagerConfig {
@@ -25,14 +42,30 @@ impl Default for ManagerConfig {
         Self {
             many_args: true,
             arg_sep: String::from(" "),
+            max_args_per_call: None,
+            shell: true,
+            timeout: None,
+            retries: 0,
+            retry_delay: 5,
         }
     }
 }
 
+/// One run of a manager command, and the items it was run with.
+pub struct Call {
+    pub line: CommandLine,
+    pub items: Vec<String>,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Manager {
+    /// The name of the manager file. (Without '.toml'.)
+    #[serde(skip)]
+    pub name: String,
     pub add: String,
+    /// Used instead of 'add' for items pinned to a version. ('#:q' is the name, '#:qversion' the version.)
+    pub add_versioned: Option<String>,
     pub remove: String,
     pub sync: Option<String>,
     pub upgrade: Option<String>,
@@ -40,6 +73,12 @@ pub struct Manager {
     pub config: ManagerConfig,
     pub hook_name: String,
     pub plural_name: String,
+    /// Managers that have to be used before this one.
+    #[serde(default)]
+    pub after: Vec<String>,
+    /// Managers that have to be used after this one.
+    #[serde(default)]
+    pub before: Vec<String>,
 }
 
 impl Manager {
@@ -47,16 +86,172 @@ impl Manager {
         items.join(&self.config.arg_sep)
     }
 
-    pub fn add(&self, items: &[String]) -> Result<(), io::Error> {
-        let many = self.config.many_args;
+    /// The commands that adding the items would run
+    pub fn add_commands(&self, items: &[Item]) -> Result<Vec<String>, io::Error> {
+        Ok(self
+            .add_calls(items)?
+            .into_iter()
+            .map(|x| x.line.to_string())
+            .collect())
+    }
+
+    /// The commands that removing the items would run
+    pub fn remove_commands(&self, items: &[Item]) -> Vec<String> {
+        self.remove_calls(items)
+            .into_iter()
+            .map(|x| x.line.to_string())
+            .collect()
+    }
+
+    fn add_calls(&self, items: &[Item]) -> Result<Vec<Call>, io::Error> {
+        let (versioned, unversioned): (Vec<&Item>, Vec<&Item>) =
+            items.iter().partition(|x| x.version.is_some());
+
+        let names: Vec<String> = unversioned.iter().map(|x| x.name.to_string()).collect();
+
+        let mut calls = self.calls(&self.add, &names);
+
+        if versioned.is_empty() {
+            return Ok(calls);
+        }
+
+        let template = match self.add_versioned {
+            Some(ref s) => s,
+            None => {
+                error!(
+                    "Some {} are pinned to a version, but the manager has no 'add_versioned' command!",
+                    self.plural_name
+                );
+
+                return Err(custom_error("Missing 'add_versioned' command!"));
+            }
+        };
+
+        // Every item has its own version, so they are always added one at a time.
+        for i in versioned {
+            calls.push(Call {
+                line: self.fill(template, &[i.name.to_string()], i.version.as_deref()),
+                items: vec![i.name.to_string()],
+            });
+        }
+
+        Ok(calls)
+    }
+
+    fn remove_calls(&self, items: &[Item]) -> Vec<Call> {
+        let names: Vec<String> = items.iter().map(|x| x.name.to_string()).collect();
+
+        self.calls(&self.remove, &names)
+    }
+
+    // Split the items into calls of the command. (All at once, in batches of 'max_args_per_call', or one by one if it uses '#:item'.)
+    fn calls(&self, template: &str, items: &[String]) -> Vec<Call> {
+        let items: Vec<String> = items
+            .iter()
+            .filter(|x| x.trim() != "")
+            .map(|x| x.to_string())
+            .collect();
+
+        if items.is_empty() {
+            return Vec::new();
+        }
+
+        let single_item = placeholder::find_all(template).contains(&Placeholder::Item);
+
+        let batch_size = match self.config.many_args && !single_item {
+            true => self.config.max_args_per_call.unwrap_or(items.len()),
+            false => 1,
+        };
+
+        items
+            .chunks(batch_size.max(1))
+            .map(|x| Call {
+                line: self.fill(template, x, None),
+                items: x.to_vec(),
+            })
+            .collect()
+    }
+
+    // Put items (and a version) into a command template.
+    //
+    // With a shell, quoted placeholders get quoted. Without one, a word that is only '#:?', '#:q' or '#:items' becomes one argument per item.
+    fn fill(&self, template: &str, items: &[String], version: Option<&str>) -> CommandLine {
+        if self.config.shell {
+            return CommandLine::Shell(placeholder::expand(template, |x| {
+                self.placeholder_value(x, items, version, true)
+            }));
+        }
+
+        let mut argv: Vec<String> = Vec::new();
+
+        for word in template.split_whitespace() {
+            if let Some(Placeholder::Items { .. }) = Placeholder::whole(word) {
+                argv.extend(items.iter().cloned());
+
+                continue;
+            }
+
+            argv.push(placeholder::expand(word, |x| {
+                self.placeholder_value(x, items, version, false)
+            }));
+        }
+
+        CommandLine::Argv(argv)
+    }
+
+    fn placeholder_value(
+        &self,
+        placeholder: &Placeholder,
+        items: &[String],
+        version: Option<&str>,
+        shell: bool,
+    ) -> String {
+        let quote = shell && placeholder.quoted();
+
+        let value = match *placeholder {
+            Placeholder::Items { .. } => {
+                return match quote {
+                    true => self.join_args(
+                        &items
+                            .iter()
+                            .map(|x| shell_quote(x))
+                            .collect::<Vec<String>>(),
+                    ),
+                    false => self.join_args(items),
+                };
+            }
+            Placeholder::Item => items.first().cloned().unwrap_or_default(),
+            Placeholder::Version { .. } => version.unwrap_or_default().to_string(),
+            Placeholder::Hostname => system::hostname().unwrap_or_default(),
+            Placeholder::Username => username(),
+            Placeholder::Generation => get_current_no_output()
+                .map(|x| x.to_string())
+                .unwrap_or_default(),
+            Placeholder::Manager => self.name.to_string(),
+            Placeholder::Env(ref name) => std::env::var(name).unwrap_or_default(),
+        };
+
+        match quote {
+            true => shell_quote(&value),
+            false => value,
+        }
+    }
+
+    // A command without items. (Sync and upgrade.)
+    fn command_line(&self, command: &str) -> CommandLine {
+        self.fill(command, &[], None)
+    }
+
+    pub fn add(&self, items: &[Item]) -> Result<(), io::Error> {
+        let calls = self.add_calls(items)?;
 
         crate::hook::run_hook_and_return_if_err!(format!("pre_{}_add", self.hook_name));
 
-        if many {
-            self.add_raw(&self.join_args(items))?;
-        } else {
-            for i in items {
-                self.add_raw(i)?;
+        for (i, call) in calls.iter().enumerate() {
+            if let Err(e) = self.add_raw(&call.line) {
+                self.report_failed_call(i, &calls);
+
+                return Err(e);
             }
         }
 
@@ -65,16 +260,16 @@ impl Manager {
         Ok(())
     }
 
-    pub fn remove(&self, items: &[String]) -> Result<(), io::Error> {
-        let many = self.config.many_args;
-
+    pub fn remove(&self, items: &[Item]) -> Result<(), io::Error> {
         crate::hook::run_hook_and_return_if_err!(format!("pre_{}_remove", self.hook_name));
 
-        if many {
-            self.remove_raw(&self.join_args(items))?;
-        } else {
-            for i in items {
-                self.remove_raw(i)?;
+        let calls = self.remove_calls(items);
+
+        for (i, call) in calls.iter().enumerate() {
+            if let Err(e) = self.remove_raw(&call.line) {
+                self.report_failed_call(i, &calls);
+
+                return Err(e);
             }
         }
 
@@ -83,12 +278,65 @@ impl Manager {
         Ok(())
     }
 
-    fn add_raw(&self, items: &str) -> Result<(), io::Error> {
-        if items.trim() == "" {
-            return Ok(());
+    // Say which items a failed call was for. (Only useful if the items were split into more than one call.)
+    fn report_failed_call(&self, index: usize, calls: &[Call]) {
+        if calls.len() < 2 {
+            return;
         }
 
-        match run_command(sed(self.add.as_str(), "#:?", items).as_str()) {
+        error!(
+            "Call {}/{} failed, with these {}: {}",
+            index + 1,
+            calls.len(),
+            self.plural_name,
+            calls[index].items.join(", ")
+        );
+
+        if index + 1 < calls.len() {
+            note!("The calls after it were not run.");
+        }
+    }
+
+    // Run a command, with the timeout and retries of the manager's config.
+    fn run(&self, command: &CommandLine) -> bool {
+        let attempts = self.config.retries + 1;
+        let timeout = self.config.timeout.map(Duration::from_secs);
+
+        for attempt in 1..=attempts {
+            // The attempt counter only means something if there can be more than one.
+            let counter = match attempts {
+                1 => String::new(),
+                _ => format!(" (Attempt {attempt}/{attempts})"),
+            };
+
+            info!("Running: {command}{counter}");
+
+            match run_command_line(command, timeout) {
+                CommandOutcome::Success => return true,
+                CommandOutcome::Failure => warning!("Command failed!{counter}"),
+                CommandOutcome::TimedOut => warning!(
+                    "Command took longer than {} seconds, and was killed!{counter}",
+                    self.config.timeout.unwrap()
+                ),
+            };
+
+            // A cancelled build job shouldn't keep trying.
+            if crate::job::current().is_some_and(|x| x.is_cancelled()) {
+                return false;
+            }
+
+            if attempt < attempts {
+                info!("Trying again in {} seconds...", self.config.retry_delay);
+
+                thread::sleep(Duration::from_secs(self.config.retry_delay));
+            }
+        }
+
+        false
+    }
+
+    fn add_raw(&self, command: &CommandLine) -> Result<(), io::Error> {
+        match self.run(command) {
             true => info!("Successfully added {}!", self.plural_name),
             false => {
                 error!("Failed to add {}!", self.plural_name);
@@ -102,12 +350,8 @@ impl Manager {
         Ok(())
     }
 
-    fn remove_raw(&self, items: &str) -> Result<(), io::Error> {
-        if items.trim() == "" {
-            return Ok(());
-        }
-
-        match run_command(sed(self.remove.as_str(), "#:?", items).as_str()) {
+    fn remove_raw(&self, command: &CommandLine) -> Result<(), io::Error> {
+        match self.run(command) {
             true => info!("Successfully removed {}!", self.plural_name),
             false => {
                 error!("Failed to remove {}!", self.plural_name);
@@ -125,7 +369,7 @@ impl Manager {
         crate::hook::run_hook_and_return_if_err!(format!("pre_{}_sync", self.hook_name));
 
         if let Some(ref s) = self.sync {
-            match run_command(s) {
+            match self.run(&self.command_line(s)) {
                 true => info!("Synced manager successfully! ('{}')", self.plural_name),
                 false => {
                     error!("Failed to sync manager! ('{}')", self.plural_name);
@@ -144,7 +388,7 @@ impl Manager {
         crate::hook::run_hook_and_return_if_err!(format!("pre_{}_upgrade", self.hook_name));
 
         if let Some(ref s) = self.upgrade {
-            match run_command(s) {
+            match self.run(&self.command_line(s)) {
                 true => info!("Successfully upgraded {}!", self.plural_name),
                 false => {
                     error!("Failed to upgrade {}!", self.plural_name);
@@ -162,16 +406,28 @@ impl Manager {
     }
 
     /// Returns a vector of all installed items that arent in the managers list
-    pub fn get_other(&self, items: &[String]) -> Result<Vec<String>, io::Error> {
+    pub fn get_other(&self, items: &[Item]) -> Result<Vec<String>, io::Error> {
         if self.list.is_some() {
             let mut others = self.list()?;
-            others.retain(|other| !items.contains(other));
+            others.retain(|other| !items.iter().any(|x| x.name == *other));
             Ok(others)
         } else {
             Ok(Vec::new())
         }
     }
 
+    /// Returns the items in the managers list that aren't installed
+    /// Expects that the list command exists for the manager
+    pub fn get_missing(&self, items: &[Item]) -> Result<Vec<Item>, io::Error> {
+        let installed = self.list()?;
+
+        Ok(items
+            .iter()
+            .filter(|x| !installed.contains(&x.name))
+            .cloned()
+            .collect())
+    }
+
     /// Gets a list of installed {plural_name}
     /// Expects that the list command exists for the manager
     pub fn list(&self) -> Result<Vec<String>, io::Error> {
@@ -193,6 +449,52 @@ impl Manager {
         self.plural_name = pn.to_string();
     }
 
+    // Every command template that 'shell' applies to, with the name of its field. ('list' always runs through bash.)
+    fn templates(&self) -> Vec<(&str, &str)> {
+        let mut templates = vec![("add", self.add.as_str()), ("remove", self.remove.as_str())];
+
+        let optional = [
+            ("add_versioned", &self.add_versioned),
+            ("sync", &self.sync),
+            ("upgrade", &self.upgrade),
+        ];
+
+        for (field, template) in optional {
+            if let Some(ref s) = template {
+                templates.push((field, s.as_str()));
+            }
+        }
+
+        templates
+    }
+
+    /// Things in the config that work, but probably shouldn't be like that.
+    pub fn check_warnings(&self) -> Vec<String> {
+        let mut warnings: Vec<String> = Vec::new();
+
+        if !self.config.shell {
+            return warnings;
+        }
+
+        for (field, template) in self.templates() {
+            let placeholders = placeholder::find_all(template);
+
+            if placeholders.contains(&Placeholder::Items { quoted: false }) {
+                warnings.push(format!(
+                    "Field '{field}' puts items in unquoted with '#:?', so the shell interprets them! (Use '#:q', or set 'shell' to false.)"
+                ));
+            }
+
+            if placeholders.contains(&Placeholder::Version { quoted: false }) {
+                warnings.push(format!(
+                    "Field '{field}' puts versions in unquoted with '#:version', so the shell interprets them! (Use '#:qversion', or set 'shell' to false.)"
+                ));
+            }
+        }
+
+        warnings
+    }
+
     pub fn check_config(&self) -> Result<(), Vec<String>> {
         let mut errors: Vec<String> = Vec::new();
 
@@ -205,6 +507,28 @@ impl Manager {
             ));
         }
 
+        if self.config.max_args_per_call == Some(0) {
+            errors.push(String::from(
+                "Field 'max_args_per_call' must be more than 0! (Leave it out for no limit.)",
+            ));
+        }
+
+        if self.config.timeout == Some(0) {
+            errors.push(String::from(
+                "Field 'timeout' must be more than 0 seconds! (Leave it out for no timeout.)",
+            ));
+        }
+
+        if !self.config.shell {
+            for (field, template) in self.templates() {
+                if SHELL_SYNTAX.iter().any(|x| template.contains(x)) {
+                    errors.push(format!(
+                        "Field '{field}' uses shell syntax, but 'shell' is false! (Set 'shell' to true, or run a script instead.)"
+                    ));
+                }
+            }
+        }
+
         if errors.len() > 0 {
             return Err(errors);
         }
@@ -229,7 +553,7 @@ pub fn load_manager_no_config_check(man: &str) -> Result<Manager, io::Error> {
         }
     };
 
-    let manager: Manager = match toml::from_str(&man_string) {
+    let mut manager: Manager = match toml::from_str(&man_string) {
         Ok(o) => o,
         Err(e) => {
             piglog::fatal!("Failed to deserialize manager! ({man})");
@@ -242,6 +566,8 @@ pub fn load_manager_no_config_check(man: &str) -> Result<Manager, io::Error> {
         }
     };
 
+    manager.name = man.to_string();
+
     Ok(manager)
 }
 
@@ -324,43 +650,228 @@ pub fn upgrade_managers(
     Ok(())
 }
 
+/// Returns the installed items that arent in the 'current' generation, for every selected manager
+pub fn get_others(managers: &Option<Vec<String>>) -> Result<Vec<(String, Vec<String>)>, io::Error> {
+    let curr_gen = gen(ConfigSide::System)?;
+
+    let man_names: Vec<String> = match managers {
+        Some(man_names) => man_names.clone(),
+        None => curr_gen.managers.keys().cloned().collect(),
+    };
+
+    let mut others: Vec<(String, Vec<String>)> = Vec::new();
+
+    for man_name in man_names {
+        let items = curr_gen
+            .managers
+            .get(&man_name)
+            .ok_or(custom_error(&format!("Failed to get manager {man_name}!")))?;
+
+        let man = load_manager(&man_name)?;
+
+        let man_others = man.get_other(&items.items)?;
+
+        others.push((man_name, man_others));
+    }
+
+    Ok(others)
+}
+
 // TODO: add info and success messages
 pub fn list_others(managers: &Option<Vec<String>>, remove: bool) -> Result<(), io::Error> {
-    let curr_gen = gen(ConfigSide::System)?;
+    let others = get_others(managers)?;
 
     info!("Installed but not specified items");
-    match managers {
-        Some(man_names) => {
-            for man_name in man_names {
-                let items = curr_gen
-                    .managers
-                    .get(man_name)
-                    .ok_or(custom_error("Failed to get manager {man_name}!"))?;
-
-                list_others_core(man_name, items, remove)?;
-            }
+
+    for (man_name, man_others) in others {
+        if man_others.is_empty() {
+            continue;
         }
-        None => {
-            for (man_name, items) in curr_gen.managers.iter() {
-                list_others_core(man_name, items, remove)?;
-            }
+
+        print_entry!(man_name, man_others);
+
+        if remove && bool_question("Remove items?", false) {
+            load_manager(&man_name)?.remove(&items_from_names(man_others))?;
         }
-    };
+    }
 
     Ok(())
 }
 
-fn list_others_core(man_name: &String, items: &Items, remove: bool) -> Result<(), io::Error> {
-    let man = load_manager(man_name)?;
+/// What checking the installed items of a generation found.
+pub struct Verification {
+    /// Manager name -> items of the generation that aren't installed. (Only managers that could be checked.)
+    pub missing: Vec<(String, Vec<Item>)>,
+    /// Managers whose 'list' command failed.
+    pub unverifiable: Vec<String>,
+}
+
+/// Returns the items of the generation that aren't installed, for every manager with a list command
+pub fn get_missing(generation: &Generation) -> Result<Verification, io::Error> {
+    let mut man_names: Vec<&String> = generation.managers.keys().collect();
+    man_names.sort();
 
-    let others = man.get_other(&items.items)?;
+    let mut verification = Verification {
+        missing: Vec::new(),
+        unverifiable: Vec::new(),
+    };
 
-    if !others.is_empty() {
-        print_entry!(man_name, others);
+    for man_name in man_names {
+        let items = &generation.managers.get(man_name).unwrap().items;
 
-        if remove && bool_question("Remove items?", false) {
-            man.remove(&others)?;
+        // Nothing can be missing, so the manager doesn't even have to be installed.
+        if items.is_empty() {
+            verification
+                .missing
+                .push((man_name.to_string(), Vec::new()));
+
+            continue;
         }
-    };
-    Ok(())
+
+        let man = load_manager(man_name)?;
+
+        if man.list.is_none() {
+            warning!("Skipping manager '{man_name}', it has no 'list' command.");
+
+            continue;
+        }
+
+        match man.get_missing(items) {
+            Ok(o) => verification.missing.push((man_name.to_string(), o)),
+            Err(_) => {
+                warning!("Couldn't verify manager '{man_name}', skipping it.");
+
+                verification.unverifiable.push(man_name.to_string());
+            }
+        };
+    }
+
+    Ok(verification)
+}
+
+// Print the items of the generation that aren't installed, and return how many there are. (And if every manager could be checked.)
+pub fn verify(generation: &Generation) -> Result<(usize, bool), io::Error> {
+    let verification = get_missing(generation)?;
+
+    let mut missing_count: usize = 0;
+
+    for (man_name, man_missing) in verification.missing {
+        if man_missing.is_empty() {
+            continue;
+        }
+
+        missing_count += man_missing.len();
+
+        print_entry!(man_name, man_missing);
+    }
+
+    if !verification.unverifiable.is_empty() {
+        warning!(
+            "These managers couldn't be verified: {}",
+            verification.unverifiable.join(", ")
+        );
+    }
+
+    Ok((missing_count, verification.unverifiable.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager(add: &str, config: &str) -> Manager {
+        toml::from_str(&format!(
+            "add = \"{add}\"\nremove = \"remove #:q\"\nplural_name = \"items\"\nhook_name = \"items\"\n\n[config]\n{config}\n"
+        ))
+        .unwrap()
+    }
+
+    // The command lines the items are added with.
+    fn lines(manager: &Manager, items: &[&str]) -> Vec<String> {
+        let items: Vec<String> = items.iter().map(|x| x.to_string()).collect();
+
+        manager
+            .calls(&manager.add, &items)
+            .into_iter()
+            .map(|x| x.line.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn placeholders_in_items_are_not_expanded() {
+        let items = vec![String::from("it's#:?"), String::from("#:qversion")];
+
+        assert_eq!(
+            manager("install #:q", "")
+                .fill("install #:q --tag=#:? #:qversion", &items, Some("1.0"))
+                .to_string(),
+            "install 'it'\\''s#:?' '#:qversion' --tag=it's#:? #:qversion 1.0"
+        );
+
+        match manager("install #:q", "shell = false").fill("install #:q --tag=#:?", &items, None) {
+            CommandLine::Argv(argv) => assert_eq!(
+                argv,
+                vec![
+                    "install",
+                    "it's#:?",
+                    "#:qversion",
+                    "--tag=it's#:? #:qversion"
+                ]
+            ),
+            CommandLine::Shell(_) => panic!("Expected a command without a shell"),
+        };
+    }
+
+    #[test]
+    fn items_are_batched_by_max_args_per_call() {
+        let items = ["a", "b", "c", "d", "e"];
+
+        assert_eq!(
+            lines(&manager("install #:q", ""), &items),
+            vec!["install a b c d e"]
+        );
+        assert_eq!(
+            lines(&manager("install #:q", "max_args_per_call = 2"), &items),
+            vec!["install a b", "install c d", "install e"]
+        );
+        assert_eq!(
+            lines(&manager("install #:q", "max_args_per_call = 5"), &items),
+            vec!["install a b c d e"]
+        );
+    }
+
+    #[test]
+    fn single_item_calls_ignore_the_batch_size() {
+        let one_by_one = vec!["install a", "install b", "install c"];
+
+        assert_eq!(
+            lines(
+                &manager("install #:item", "max_args_per_call = 2"),
+                &["a", "b", "c"]
+            ),
+            one_by_one
+        );
+        assert_eq!(
+            lines(
+                &manager("install #:q", "many_args = false\nmax_args_per_call = 2"),
+                &["a", "b", "c"]
+            ),
+            one_by_one
+        );
+    }
+
+    #[test]
+    fn calls_keep_their_items() {
+        let manager = manager("install #:q", "max_args_per_call = 2");
+        let items: Vec<String> = ["a", " ", "b", "c"].iter().map(|x| x.to_string()).collect();
+
+        let calls = manager.calls(&manager.add, &items);
+
+        // Blank items are left out.
+        assert_eq!(
+            calls.iter().map(|x| x.items.clone()).collect::<Vec<_>>(),
+            vec![vec!["a", "b"], vec!["c"]]
+        );
+        assert!(manager.calls(&manager.add, &[]).is_empty());
+    }
 }
@@ -2,7 +2,10 @@
 
 use piglog::prelude::*;
 
+use crate::generation::imports::Declaration;
+use crate::generation::metadata::Metadata;
 use crate::generation::Generation;
+use crate::library::format_timestamp;
 use crate::obj_print_boilerplate::macros::*;
 
 pub fn generation(gen: &Generation) {
@@ -12,3 +15,57 @@ pub fn generation(gen: &Generation) {
         print_entry!(i, gen.managers.get(i).unwrap().items);
     }
 }
+
+pub fn declarations(declarations: &[Declaration]) {
+    println!();
+
+    for i in declarations.iter() {
+        piglog::info!("{} ({}):", i.item, i.manager);
+        piglog::generic!("Declared in: {}", i.file);
+        piglog::generic!("Import chain: {}", i.chain.join(" -> "));
+
+        if let Some(ref file) = i.excluded_by {
+            piglog::generic!("Excluded by: {file}");
+        }
+
+        println!();
+    }
+}
+
+pub fn generation_header(number: usize, commit: &str, meta: &Option<Metadata>) {
+    println!();
+
+    piglog::info!("Generation {number}:");
+
+    let mut commit_lines = commit.lines();
+
+    piglog::generic!("Commit: {}", commit_lines.next().unwrap_or(""));
+
+    // The rest of a multi-line commit message.
+    for line in commit_lines {
+        println!("    {line}");
+    }
+
+    match meta {
+        Some(meta) => {
+            piglog::generic!("Created: {}", format_timestamp(meta.created));
+            piglog::generic!("Host: {}", meta.hostname);
+            piglog::generic!("User: {}", meta.username);
+            piglog::generic!("Rebos version: {}", meta.rebos_version);
+
+            if let Some(parent) = meta.parent {
+                piglog::generic!("Parent: {parent}");
+            }
+
+            if let Some(ref git) = meta.git {
+                match git.dirty {
+                    true => {
+                        piglog::generic!("Config revision: {} (with uncommitted changes)", git.head)
+                    }
+                    false => piglog::generic!("Config revision: {}", git.head),
+                };
+            }
+        }
+        None => piglog::note!("No metadata. (Made by an older version of Rebos.)"),
+    };
+}
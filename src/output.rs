@@ -0,0 +1,184 @@
+#![allow(dead_code)]
+
+// The JSON printed by read commands when using '--output json'.
+//
+// Scripts depend on these shapes, so fields should only ever be added, never renamed or removed!
+// Maps are sorted by key, so the output is the same between runs.
+
+use hashbrown::HashMap;
+use piglog::prelude::*;
+use piglog::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io;
+
+use crate::generation;
+use crate::generation::metadata::Metadata;
+use crate::generation::{Generation, Item};
+use crate::library::*;
+
+/// `gen diff`: What changed between two generations.
+#[derive(Serialize, Debug)]
+pub struct Diff {
+    pub old: DiffSide,
+    pub new: DiffSide,
+    /// Manager name -> changes to its items.
+    pub managers: BTreeMap<String, Changes>,
+}
+
+/// One of the generations being compared by `gen diff`.
+#[derive(Serialize, Debug)]
+pub struct DiffSide {
+    /// Left out for the user config. (`gen diff --user`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number: Option<usize>,
+    pub commit: String,
+}
+
+/// Items that were added to and removed from a manager, and items whose version changed.
+#[derive(Serialize, Debug, Default)]
+pub struct Changes {
+    pub add: Vec<String>,
+    pub remove: Vec<String>,
+    pub change: Vec<VersionChange>,
+    /// Item name -> version, for the added and removed items that are pinned.
+    pub versions: BTreeMap<String, String>,
+}
+
+/// An item whose pinned version changed. (A missing version means it is not pinned.)
+#[derive(Serialize, Debug)]
+pub struct VersionChange {
+    pub name: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// `gen info`: The generation in the user's config.
+#[derive(Serialize, Debug)]
+pub struct Info {
+    pub imports: Vec<String>,
+    /// Manager name -> item names.
+    pub managers: BTreeMap<String, Vec<String>>,
+    /// Manager name -> item name -> version, for the items that are pinned.
+    pub versions: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// `gen show`: A system generation.
+#[derive(Serialize, Debug)]
+pub struct Show {
+    pub number: usize,
+    pub commit: String,
+    /// None for generations made before metadata existed.
+    pub metadata: Option<Metadata>,
+    /// Manager name -> item names.
+    pub managers: BTreeMap<String, Vec<String>>,
+    /// Manager name -> item name -> version, for the items that are pinned.
+    pub versions: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// `gen latest`: The latest generation number.
+#[derive(Serialize, Debug)]
+pub struct Latest {
+    pub latest: usize,
+}
+
+/// `status`: The user config compared to the 'current' generation, and the 'current' generation compared to the built one.
+#[derive(Serialize, Debug)]
+pub struct Status {
+    pub current: Option<usize>,
+    pub built: Option<usize>,
+    pub current_is_built: bool,
+    /// Manager name -> changes in the user config that aren't committed yet.
+    pub uncommitted: BTreeMap<String, Changes>,
+}
+
+/// `managers list-others`: Manager name -> installed items that aren't in the generation.
+pub type Others = BTreeMap<String, Vec<String>>;
+
+/// `gen current verify`: Manager name -> items of the built generation that aren't installed.
+pub type Missing = BTreeMap<String, Vec<Item>>;
+
+pub fn changes(history: &HashMap<String, Vec<History>>) -> BTreeMap<String, Changes> {
+    let mut changes: BTreeMap<String, Changes> = BTreeMap::new();
+
+    for (man, diffs) in history.iter() {
+        let entry = changes.entry(man.to_string()).or_default();
+
+        for i in diffs.iter() {
+            match i.mode {
+                HistoryMode::Add => entry.add.push(i.item.name.to_string()),
+                HistoryMode::Remove => entry.remove.push(i.item.name.to_string()),
+                HistoryMode::Change => entry.change.push(VersionChange {
+                    name: i.item.name.to_string(),
+                    from: i.old.as_ref().and_then(|x| x.version.clone()),
+                    to: i.item.version.clone(),
+                }),
+            };
+
+            if let (HistoryMode::Add | HistoryMode::Remove, Some(version)) =
+                (&i.mode, &i.item.version)
+            {
+                entry
+                    .versions
+                    .insert(i.item.name.to_string(), version.to_string());
+            }
+        }
+    }
+
+    changes
+}
+
+pub fn status(status: &generation::Status) -> Status {
+    Status {
+        current: status.current,
+        built: status.built,
+        current_is_built: status.current_is_built(),
+        uncommitted: changes(&status.uncommitted),
+    }
+}
+
+pub fn info(gen: &Generation) -> Info {
+    let mut versions: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+
+    for (man, items) in gen.managers.iter() {
+        for i in items.items.iter() {
+            if let Some(ref version) = i.version {
+                versions
+                    .entry(man.to_string())
+                    .or_default()
+                    .insert(i.name.to_string(), version.to_string());
+            }
+        }
+    }
+
+    Info {
+        imports: gen.imports.clone(),
+        managers: gen
+            .managers
+            .iter()
+            .map(|(k, v)| {
+                (
+                    k.to_string(),
+                    v.items.iter().map(|x| x.name.to_string()).collect(),
+                )
+            })
+            .collect(),
+        versions,
+    }
+}
+
+// Print a value as pretty JSON.
+pub fn print_json<T: Serialize>(value: &T) -> Result<(), io::Error> {
+    match serde_json::to_string_pretty(value) {
+        Ok(o) => {
+            println!("{o}");
+
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to convert output to JSON! ({e})");
+
+            Err(custom_error("Failed to convert output to JSON!"))
+        }
+    }
+}
@@ -0,0 +1,167 @@
+/// Something in a manager command template that gets replaced. (Written as '#:NAME'.)
+#[derive(PartialEq, Clone, Debug)]
+pub enum Placeholder {
+    /// All items of the call. ('#:items' and '#:q' are quoted, '#:?' isn't.)
+    Items {
+        quoted: bool,
+    },
+    /// A single item. (Commands using it are run once per item.)
+    Item,
+    /// The version of a pinned item. ('#:qversion' is quoted, '#:version' isn't.)
+    Version {
+        quoted: bool,
+    },
+    Hostname,
+    Username,
+    /// The 'current' generation.
+    Generation,
+    /// The name of the manager.
+    Manager,
+    /// An environment variable. ('#:env(NAME)')
+    Env(String),
+}
+
+impl Placeholder {
+    // Read a placeholder from the start of the text after '#:'. (Returns it, and how long it is.)
+    fn parse(text: &str) -> Option<(Self, usize)> {
+        if let Some(rest) = text.strip_prefix("env(") {
+            let end = rest.find(')')?;
+
+            return Some((Self::Env(rest[..end].to_string()), end + "env()".len()));
+        }
+
+        // Longer names first, so '#:items' isn't read as '#:item' and so on.
+        let names = [
+            ("qversion", Self::Version { quoted: true }),
+            ("version", Self::Version { quoted: false }),
+            ("items", Self::Items { quoted: true }),
+            ("item", Self::Item),
+            ("hostname", Self::Hostname),
+            ("username", Self::Username),
+            ("generation", Self::Generation),
+            ("manager", Self::Manager),
+            ("q", Self::Items { quoted: true }),
+            ("?", Self::Items { quoted: false }),
+        ];
+
+        names
+            .into_iter()
+            .find(|(name, _)| text.starts_with(name))
+            .map(|(name, placeholder)| (placeholder, name.len()))
+    }
+
+    /// Does the value get quoted when the command is run through a shell?
+    pub fn quoted(&self) -> bool {
+        match *self {
+            Self::Items { quoted } | Self::Version { quoted } => quoted,
+            _ => true,
+        }
+    }
+
+    /// The placeholder, if the whole text is one.
+    pub fn whole(text: &str) -> Option<Self> {
+        let rest = text.strip_prefix("#:")?;
+
+        match Self::parse(rest) {
+            Some((placeholder, len)) if len == rest.len() => Some(placeholder),
+            _ => None,
+        }
+    }
+}
+
+/// Every placeholder in the template.
+pub fn find_all(template: &str) -> Vec<Placeholder> {
+    let mut found: Vec<Placeholder> = Vec::new();
+
+    expand(template, |x| {
+        found.push(x.clone());
+
+        String::new()
+    });
+
+    found
+}
+
+/// Replace every placeholder in the template with its value. (Unknown ones are left as they are.)
+pub fn expand(template: &str, mut value: impl FnMut(&Placeholder) -> String) -> String {
+    let mut expanded = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("#:") {
+        expanded.push_str(&rest[..start]);
+
+        let after = &rest[start + 2..];
+
+        match Placeholder::parse(after) {
+            Some((placeholder, len)) => {
+                expanded.push_str(&value(&placeholder));
+                rest = &after[len..];
+            }
+            None => {
+                expanded.push_str("#:");
+                rest = after;
+            }
+        }
+    }
+
+    expanded.push_str(rest);
+
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Show every placeholder by its debug name, to see what was read.
+    fn names(template: &str) -> String {
+        expand(template, |x| format!("<{x:?}>"))
+    }
+
+    #[test]
+    fn longer_names_win() {
+        assert_eq!(names("#:items"), "<Items { quoted: true }>");
+        assert_eq!(names("#:item"), "<Item>");
+        assert_eq!(names("#:itemx"), "<Item>x");
+        assert_eq!(names("#:qversion"), "<Version { quoted: true }>");
+        assert_eq!(names("#:version"), "<Version { quoted: false }>");
+        assert_eq!(names("#:q"), "<Items { quoted: true }>");
+        assert_eq!(names("#:?"), "<Items { quoted: false }>");
+    }
+
+    #[test]
+    fn env_names() {
+        assert_eq!(names("#:env(HOME)/x"), "<Env(\"HOME\")>/x");
+        assert_eq!(names("#:env()"), "<Env(\"\")>");
+        // Without the closing bracket, it isn't a placeholder.
+        assert_eq!(names("#:env(HOME"), "#:env(HOME");
+    }
+
+    #[test]
+    fn unknown_text_is_kept() {
+        assert_eq!(names("echo #:nope # #: done"), "echo #:nope # #: done");
+        assert_eq!(names("a#:?b#:"), "a<Items { quoted: false }>b#:");
+    }
+
+    #[test]
+    fn values_are_not_expanded_again() {
+        assert_eq!(
+            expand("#:? #:version", |_| String::from("#:version")),
+            "#:version #:version"
+        );
+    }
+
+    #[test]
+    fn find_all_and_whole() {
+        assert_eq!(
+            find_all("install #:item --log #:env(LOG)"),
+            vec![Placeholder::Item, Placeholder::Env(String::from("LOG"))]
+        );
+        assert_eq!(
+            Placeholder::whole("#:items"),
+            Some(Placeholder::Items { quoted: true })
+        );
+        assert_eq!(Placeholder::whole("#:items,"), None);
+        assert_eq!(Placeholder::whole("items"), None);
+    }
+}
@@ -42,7 +42,22 @@ pub fn gens() -> Path {
     base().add_str("generations")
 }
 
+/// Journal of the build that is in progress (or was interrupted)
+pub fn build_journal() -> Path {
+    base().add_str("build_journal.toml")
+}
+
+/// Commit message that is being written in the user's editor
+pub fn commit_message() -> Path {
+    base().add_str("COMMIT_MESSAGE")
+}
+
 /// User's Rebos config directory
 pub fn base_user() -> Path {
     location::config().unwrap().add_str("rebos")
 }
+
+/// Rebos settings file
+pub fn settings() -> Path {
+    base_user().add_str("settings.toml")
+}
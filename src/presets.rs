@@ -0,0 +1,424 @@
+use fspp::*;
+use piglog::prelude::*;
+use piglog::*;
+use std::io;
+
+use crate::library::*;
+use crate::places;
+
+/// A bundled manager configuration for a common package manager.
+pub struct Preset {
+    pub name: &'static str,
+    /// Distro IDs (from /etc/os-release) this is the system package manager of.
+    pub distros: &'static [&'static str],
+    pub config: &'static str,
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "apt",
+        distros: &["debian", "ubuntu", "linuxmint", "pop", "raspbian"],
+        config: APT,
+    },
+    Preset {
+        name: "dnf",
+        distros: &["fedora", "rhel", "centos", "rocky", "almalinux"],
+        config: DNF,
+    },
+    Preset {
+        name: "pacman",
+        distros: &["arch", "manjaro", "endeavouros"],
+        config: PACMAN,
+    },
+    Preset {
+        name: "paru",
+        distros: &[],
+        config: PARU,
+    },
+    Preset {
+        name: "yay",
+        distros: &[],
+        config: YAY,
+    },
+    Preset {
+        name: "zypper",
+        distros: &[
+            "opensuse",
+            "suse",
+            "opensuse-tumbleweed",
+            "opensuse-leap",
+            "sles",
+        ],
+        config: ZYPPER,
+    },
+    Preset {
+        name: "apk",
+        distros: &["alpine", "postmarketos"],
+        config: APK,
+    },
+    Preset {
+        name: "xbps",
+        distros: &["void"],
+        config: XBPS,
+    },
+    Preset {
+        name: "flatpak",
+        distros: &[],
+        config: FLATPAK,
+    },
+    Preset {
+        name: "cargo",
+        distros: &[],
+        config: CARGO,
+    },
+    Preset {
+        name: "pipx",
+        distros: &[],
+        config: PIPX,
+    },
+    Preset {
+        name: "npm",
+        distros: &[],
+        config: NPM,
+    },
+    Preset {
+        name: "snap",
+        distros: &[],
+        config: SNAP,
+    },
+    Preset {
+        name: "brew",
+        distros: &[],
+        config: BREW,
+    },
+];
+
+pub fn get(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|x| x.name == name)
+}
+
+pub fn names() -> Vec<&'static str> {
+    PRESETS.iter().map(|x| x.name).collect()
+}
+
+// The preset for the system package manager. (Based on the distro IDs, the distro itself first.)
+pub fn for_distro(distros: &[String]) -> Option<&'static Preset> {
+    distros
+        .iter()
+        .find_map(|d| PRESETS.iter().find(|x| x.distros.contains(&d.as_str())))
+}
+
+// Write a preset to 'managers/NAME.toml'.
+pub fn add(preset_name: &str, name: Option<&str>, force: bool) -> Result<(), io::Error> {
+    let preset = match get(preset_name) {
+        Some(s) => s,
+        None => {
+            error!("There is no preset called '{preset_name}'!");
+            note!("Available presets: {}", names().join(", "));
+
+            return Err(custom_error("Unknown preset!"));
+        }
+    };
+
+    let name = name.unwrap_or(preset.name);
+
+    if name.is_empty() || name.starts_with('.') || name.contains('/') {
+        error!("Invalid manager name! ({name})");
+        note!("Manager names can't be empty, start with '.' or contain '/'.");
+
+        return Err(custom_error("Invalid manager name!"));
+    }
+
+    let path = places::base_user().add_str(&format!("managers/{name}.toml"));
+
+    if path.exists() && !force {
+        error!("Manager '{name}' already exists! (Use '--force' to overwrite it.)");
+
+        return Err(custom_error("Manager already exists!"));
+    }
+
+    if let Err(e) = file::write(preset.config, &path) {
+        error!("Failed to write manager file: {}", path.to_string());
+
+        return Err(e);
+    }
+
+    success!("Added manager '{name}' from the '{}' preset!", preset.name);
+    note!("Add items for it under '[managers.{name}]' in gen.toml.");
+
+    Ok(())
+}
+
+const APT: &str = r#"# Apt (Debian, Ubuntu, ...)
+
+add = "sudo apt-get install -y #:q"
+add_versioned = "sudo apt-get install -y #:q=#:qversion"
+remove = "sudo apt-get remove -y #:q"
+sync = "sudo apt-get update"
+upgrade = "sudo apt-get upgrade -y"
+list = "apt-mark showmanual"
+
+plural_name = "system packages"
+
+hook_name = "system_packages"
+
+[config]
+many_args = true
+"#;
+
+const DNF: &str = r#"# DNF (Fedora, RHEL, ...)
+
+add = "sudo dnf install -y #:q"
+add_versioned = "sudo dnf install -y #:q-#:qversion"
+remove = "sudo dnf remove -y #:q"
+sync = "sudo dnf makecache"
+upgrade = "sudo dnf upgrade -y"
+list = "dnf repoquery --userinstalled --queryformat '%{name}\n'"
+
+plural_name = "system packages"
+
+hook_name = "system_packages"
+
+[config]
+many_args = true
+"#;
+
+const PACMAN: &str = r#"# Pacman (Arch Linux, ...)
+
+add = "sudo pacman -S --needed --noconfirm #:q"
+remove = "sudo pacman -Rs --noconfirm #:q"
+upgrade = "sudo pacman -Syu --noconfirm"
+list = "pacman -Qqe"
+
+plural_name = "system packages"
+
+hook_name = "system_packages"
+
+[config]
+many_args = true
+"#;
+
+const PARU: &str = r#"# Paru (Arch Linux, with the AUR)
+
+add = "paru -S --needed --noconfirm #:q"
+remove = "paru -Rs --noconfirm #:q"
+upgrade = "paru -Syu --noconfirm"
+list = "paru -Qqe"
+
+plural_name = "system packages"
+
+hook_name = "system_packages"
+
+[config]
+many_args = true
+"#;
+
+const YAY: &str = r#"# Yay (Arch Linux, with the AUR)
+
+add = "yay -S --needed --noconfirm #:q"
+remove = "yay -Rs --noconfirm #:q"
+upgrade = "yay -Syu --noconfirm"
+list = "yay -Qqe"
+
+plural_name = "system packages"
+
+hook_name = "system_packages"
+
+[config]
+many_args = true
+"#;
+
+const ZYPPER: &str = r#"# Zypper (openSUSE, SLES)
+
+add = "sudo zypper --non-interactive install #:q"
+add_versioned = "sudo zypper --non-interactive install #:q=#:qversion"
+remove = "sudo zypper --non-interactive remove #:q"
+sync = "sudo zypper --non-interactive refresh"
+upgrade = "sudo zypper --non-interactive update"
+list = "zypper --quiet packages --userinstalled | awk -F '|' 'NR > 2 { gsub(/ /, \"\", $3); print $3 }'"
+
+plural_name = "system packages"
+
+hook_name = "system_packages"
+
+[config]
+many_args = true
+"#;
+
+const APK: &str = r#"# Apk (Alpine Linux, ...)
+
+add = "sudo apk add #:q"
+add_versioned = "sudo apk add #:q=#:qversion"
+remove = "sudo apk del #:q"
+sync = "sudo apk update"
+upgrade = "sudo apk upgrade"
+list = "sed 's/[<>=~].*//' /etc/apk/world"
+
+plural_name = "system packages"
+
+hook_name = "system_packages"
+
+[config]
+many_args = true
+"#;
+
+const XBPS: &str = r#"# XBPS (Void Linux)
+
+add = "sudo xbps-install -y #:q"
+remove = "sudo xbps-remove -Ry #:q"
+sync = "sudo xbps-install -S"
+upgrade = "sudo xbps-install -uy"
+list = "xbps-query -m | sed 's/-[^-]*$//'"
+
+plural_name = "system packages"
+
+hook_name = "system_packages"
+
+[config]
+many_args = true
+"#;
+
+pub const FLATPAK: &str = r#"# Flatpak
+
+add = "flatpak install -y #:q"
+remove = "flatpak uninstall -y #:q"
+upgrade = "flatpak update -y"
+list = "flatpak list --app --columns=application"
+
+plural_name = "flatpaks"
+
+hook_name = "flatpaks"
+
+[config]
+many_args = true
+"#;
+
+pub const CARGO: &str = r#"# Cargo
+
+add = "cargo install #:q"
+add_versioned = "cargo install #:q --version #:qversion"
+remove = "cargo uninstall #:q"
+list = "cargo install --list | grep -v '^ ' | cut -d ' ' -f 1"
+
+plural_name = "crates"
+
+hook_name = "crates"
+
+[config]
+many_args = true
+"#;
+
+const PIPX: &str = r#"# Pipx
+
+add = "pipx install #:q"
+add_versioned = "pipx install #:q==#:qversion"
+remove = "pipx uninstall #:item"
+upgrade = "pipx upgrade-all"
+list = "pipx list --short | cut -d ' ' -f 1"
+
+plural_name = "python applications"
+
+hook_name = "python_applications"
+
+[config]
+many_args = true
+"#;
+
+const NPM: &str = r#"# Npm (Global packages)
+
+add = "npm install -g #:q"
+add_versioned = "npm install -g #:q@#:qversion"
+remove = "npm uninstall -g #:q"
+upgrade = "npm update -g"
+list = "npm ls -g --depth=0 --parseable | tail -n +2 | sed 's|.*/node_modules/||'"
+
+plural_name = "npm packages"
+
+hook_name = "npm_packages"
+
+[config]
+many_args = true
+"#;
+
+const SNAP: &str = r#"# Snap
+
+add = "sudo snap install #:q"
+remove = "sudo snap remove #:q"
+upgrade = "sudo snap refresh"
+list = "snap list | tail -n +2 | cut -d ' ' -f 1"
+
+plural_name = "snaps"
+
+hook_name = "snaps"
+
+[config]
+many_args = true
+"#;
+
+const BREW: &str = r#"# Homebrew
+
+add = "brew install #:q"
+remove = "brew uninstall #:q"
+sync = "brew update"
+upgrade = "brew upgrade"
+list = "brew leaves --installed-on-request; brew list --cask"
+
+plural_name = "brew packages"
+
+hook_name = "brew_packages"
+
+[config]
+many_args = true
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::management::Manager;
+
+    #[test]
+    fn presets_are_valid_managers() {
+        for preset in PRESETS {
+            let manager: Manager = match toml::from_str(preset.config) {
+                Ok(o) => o,
+                Err(e) => panic!("Preset '{}' doesn't deserialize: {e}", preset.name),
+            };
+
+            assert!(
+                manager.check_config().is_ok(),
+                "Preset '{}' fails the config check: {:?}",
+                preset.name,
+                manager.check_config()
+            );
+            assert_eq!(
+                manager.check_warnings(),
+                Vec::<String>::new(),
+                "Preset '{}' has warnings",
+                preset.name
+            );
+            assert!(
+                manager.list.is_some(),
+                "Preset '{}' has no 'list' command",
+                preset.name
+            );
+        }
+    }
+
+    #[test]
+    fn system_preset_from_distro_ids() {
+        let ids = |x: &[&str]| x.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+
+        assert_eq!(for_distro(&ids(&["ubuntu", "debian"])).unwrap().name, "apt");
+        assert_eq!(
+            for_distro(&ids(&["opensuse-tumbleweed", "opensuse", "suse"]))
+                .unwrap()
+                .name,
+            "zypper"
+        );
+        assert_eq!(
+            for_distro(&ids(&["somethingelse", "arch"])).unwrap().name,
+            "pacman"
+        );
+        assert!(for_distro(&ids(&["nixos"])).is_none());
+    }
+}
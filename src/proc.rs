@@ -1,3 +1,11 @@
+use std::fs::File;
+use std::io;
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, Command};
+use std::thread;
+use std::time::Duration;
+
 const ID_ENV_NAME: &str = "__REBOS_PROC_ID_THIS_ENV_VAR_SHOULD_NOT_BE_OVERWRITTEN__";
 
 pub fn init_proc_id() {
@@ -9,3 +17,150 @@ pub fn init_proc_id() {
 pub fn get_proc_id() -> String {
     std::env::var(ID_ENV_NAME).unwrap()
 }
+
+/// Start a command in a process group of its own, so stopping it also stops everything it
+/// started. With 'foreground', the group gets the terminal while it runs. (If Rebos has it.)
+pub fn spawn_in_group(command: &mut Command, foreground: bool) -> io::Result<(Child, Terminal)> {
+    command.process_group(0);
+
+    let tty = match foreground {
+        true => controlling_terminal(),
+        false => None,
+    };
+
+    if let Some(fd) = tty.as_ref().map(|x| x.as_raw_fd()) {
+        // The command has to have the terminal before it runs, or reading from it (like a
+        // password prompt) would stop it.
+        unsafe {
+            command.pre_exec(move || {
+                libc::setpgid(0, 0);
+                give_terminal(fd, libc::getpid());
+                Ok(())
+            });
+        }
+    }
+
+    let child = command.spawn()?;
+
+    Ok((child, Terminal { tty }))
+}
+
+/// The terminal while a command has it. (Given back to Rebos when dropped.)
+pub struct Terminal {
+    tty: Option<File>,
+}
+
+impl Terminal {
+    // If the command (the group leader) got stopped (Ctrl+Z), stop Rebos too, and continue the
+    // command along with it.
+    pub fn forward_stop(&self, leader: u32) {
+        let fd = match self.tty {
+            Some(ref s) => s.as_raw_fd(),
+            None => return,
+        };
+
+        let group = leader as libc::pid_t;
+
+        unsafe {
+            let mut info: libc::siginfo_t = std::mem::zeroed();
+
+            // Only reports stopped commands, an exit is left for the normal wait.
+            let result = libc::waitid(
+                libc::P_PID,
+                group as libc::id_t,
+                &mut info,
+                libc::WSTOPPED | libc::WNOHANG,
+            );
+
+            if result != 0 || info.si_pid() == 0 {
+                return;
+            }
+
+            give_terminal(fd, libc::getpgrp());
+            libc::raise(libc::SIGTSTP);
+
+            // Continued. ('fg')
+            give_terminal(fd, group);
+            libc::kill(-group, libc::SIGCONT);
+        }
+    }
+
+    // If the command was interrupted (Ctrl+C), interrupt Rebos too. (Only the command got the signal.)
+    pub fn forward_interrupt(self, child: &mut Child) {
+        if self.tty.is_none() {
+            return;
+        }
+
+        let interrupted = child
+            .try_wait()
+            .is_ok_and(|x| x.is_some_and(|x| x.signal() == Some(libc::SIGINT)));
+
+        drop(self);
+
+        if interrupted {
+            unsafe {
+                libc::raise(libc::SIGINT);
+            }
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if let Some(ref tty) = self.tty {
+            give_terminal(tty.as_raw_fd(), unsafe { libc::getpgrp() });
+        }
+    }
+}
+
+/// Stop a command started with 'spawn_in_group', and everything it started. (Whatever ignores
+/// SIGTERM for 2 seconds gets SIGKILL.)
+pub fn kill_group(child: &mut Child) {
+    let group = child.id() as libc::pid_t;
+
+    unsafe {
+        libc::kill(-group, libc::SIGTERM);
+    }
+
+    for _ in 0..20 {
+        // Reap the command, so the group is gone once everything in it exited.
+        let _ = child.try_wait();
+
+        if unsafe { libc::kill(-group, 0) } != 0 {
+            return;
+        }
+
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    unsafe {
+        libc::kill(-group, libc::SIGKILL);
+    }
+
+    let _ = child.wait();
+}
+
+// The terminal, if Rebos is in its foreground.
+fn controlling_terminal() -> Option<File> {
+    let tty = File::options()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+
+    match unsafe { libc::tcgetpgrp(tty.as_raw_fd()) == libc::getpgrp() } {
+        true => Some(tty),
+        false => None,
+    }
+}
+
+// Make a process group the foreground of the terminal. (Async-signal-safe, it runs between fork and exec.)
+fn give_terminal(fd: RawFd, group: libc::pid_t) {
+    unsafe {
+        // Background processes changing this get stopped, unless they ignore SIGTTOU.
+        let previous = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+
+        libc::tcsetpgrp(fd, group);
+        libc::signal(libc::SIGTTOU, previous);
+    }
+}
@@ -0,0 +1,70 @@
+#![allow(dead_code)]
+
+use fspp::*;
+use piglog::prelude::*;
+use piglog::*;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+use crate::library::*;
+use crate::places;
+
+/// Rebos settings. (settings.toml in the user's config directory, every field is optional.)
+#[derive(PartialEq, Serialize, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct Settings {
+    pub gc: GcSettings,
+    pub git: GitSettings,
+}
+
+/// Retention policy for 'gen gc'. A generation is kept if any of the rules keeps it.
+#[derive(PartialEq, Serialize, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct GcSettings {
+    /// Keep the newest N generations.
+    pub keep_last: Option<usize>,
+    /// Keep generations created in the last N days.
+    pub keep_days: Option<u64>,
+    /// Keep the last N generations that were built.
+    pub keep_built_history: Option<usize>,
+}
+
+/// Git integration for the config directory. (Only does anything if the config directory is a git repository.)
+#[derive(PartialEq, Serialize, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct GitSettings {
+    /// Record the config's git revision in the metadata of every new generation.
+    pub record: bool,
+    /// Commit the config with the generation's commit message before recording it.
+    pub auto_commit: bool,
+}
+
+// Load the settings. (Defaults if there is no settings file.)
+pub fn load() -> Result<Settings, io::Error> {
+    let path = places::settings();
+
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+
+    let settings_string = match file::read(&path) {
+        Ok(o) => o,
+        Err(e) => {
+            error!("Failed to read settings file! ({})", path.to_string());
+            return Err(e);
+        }
+    };
+
+    match toml::from_str(&settings_string) {
+        Ok(o) => Ok(o),
+        Err(e) => {
+            error!(
+                "Failed to deserialize settings file! ({})",
+                path.to_string()
+            );
+            error!("TOML Error: {e:#?}");
+
+            Err(custom_error("Failed to deserialize settings!"))
+        }
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
 use std::io;
+use std::sync::OnceLock;
 use piglog::prelude::*;
 use piglog::error;
 
@@ -21,3 +22,70 @@ pub fn hostname() -> Result<String, io::Error> {
         },
     });
 }
+
+/// Facts about the host, used to decide which conditional items apply
+pub struct HostFacts {
+    /// 'ID' and 'ID_LIKE' from /etc/os-release (Example: ["fedora"], or ["ubuntu", "debian"])
+    pub distros: Vec<String>,
+    /// Output of `uname -m` (Example: "x86_64")
+    pub arch: String,
+}
+
+static HOST_FACTS: OnceLock<HostFacts> = OnceLock::new();
+
+// The facts are only gathered once per run.
+pub fn host_facts() -> Result<&'static HostFacts, io::Error> {
+    if let Some(facts) = HOST_FACTS.get() {
+        return Ok(facts);
+    }
+
+    let facts = HostFacts {
+        distros: distros(),
+        arch: arch()?,
+    };
+
+    Ok(HOST_FACTS.get_or_init(|| facts))
+}
+
+// Read the distro IDs from /etc/os-release. (The distro itself first, then the ones it is like.)
+pub fn distros() -> Vec<String> {
+    let os_release = match std::fs::read_to_string("/etc/os-release") {
+        Ok(o) => o,
+        Err(_e) => {
+            piglog::warning!("Failed to read '/etc/os-release', can't tell what distro this is!");
+            return Vec::new();
+        },
+    };
+
+    let mut id: Vec<String> = Vec::new();
+    let mut id_like: Vec<String> = Vec::new();
+
+    for line in os_release.lines() {
+        let (key, value) = match line.split_once('=') {
+            Some(s) => s,
+            None => continue,
+        };
+
+        let value = value.trim().trim_matches('"').trim_matches('\'');
+
+        match key.trim() {
+            "ID" => id.push(value.to_lowercase()),
+            "ID_LIKE" => id_like.extend(value.split_whitespace().map(|x| x.to_lowercase())),
+            _ => (),
+        };
+    }
+
+    id.extend(id_like);
+
+    id
+}
+
+pub fn arch() -> Result<String, io::Error> {
+    match crate::library::run_command_with_output("uname -m") {
+        Some(s) => Ok(s.trim().to_string()),
+        None => {
+            error!("Failed to get system architecture! ('uname -m')");
+            Err(custom_error("Failed to get system architecture!"))
+        },
+    }
+}
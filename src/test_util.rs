@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::{Mutex, MutexGuard};
+
+// Tests that change environment variables can't run at the same time.
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+/// Lock the environment, and point the config and state directories (and git's identity) into the directory.
+pub fn sandbox(dir: &std::path::Path) -> MutexGuard<'static, ()> {
+    let guard = ENV_LOCK.lock().unwrap_or_else(|x| x.into_inner());
+
+    std::env::set_var("XDG_CONFIG_HOME", dir.join("config"));
+    std::env::set_var("XDG_STATE_HOME", dir.join("state"));
+
+    for (name, value) in [
+        ("GIT_AUTHOR_NAME", "Rebos Test"),
+        ("GIT_AUTHOR_EMAIL", "test@rebos.invalid"),
+        ("GIT_COMMITTER_NAME", "Rebos Test"),
+        ("GIT_COMMITTER_EMAIL", "test@rebos.invalid"),
+    ] {
+        std::env::set_var(name, value);
+    }
+
+    guard
+}
+
+/// A new, empty directory for a test.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rebos-test-{}-{name}", std::process::id()));
+
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    dir
+}
+
+/// Run git in a directory, and panic if it fails.
+pub fn git(dir: &std::path::Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Make the directory a git repository, and commit everything in it. (Returns the commit.)
+pub fn repo(dir: &std::path::Path, message: &str) -> String {
+    std::fs::create_dir_all(dir).unwrap();
+    git(dir, &["init", "--quiet"]);
+
+    commit(dir, message)
+}
+
+/// Commit everything in the repository. (Returns the commit.)
+pub fn commit(dir: &std::path::Path, message: &str) -> String {
+    git(dir, &["add", "--all"]);
+    git(
+        dir,
+        &["commit", "--quiet", "--allow-empty", "--message", message],
+    );
+
+    git(dir, &["rev-parse", "HEAD"]).trim().to_string()
+}
+
+/// Write a file, creating its parent directories.
+pub fn write(path: &std::path::Path, contents: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, contents).unwrap();
+}